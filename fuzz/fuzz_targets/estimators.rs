@@ -0,0 +1,35 @@
+//! Feeds arbitrary offsets, out of order and repeated included, into every estimator
+//! Run with `cargo fuzz run estimators` from the repository root
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// the app is a binary, so the estimators are pulled in as source
+#[allow(dead_code)]
+#[path = "../../src/bpm.rs"]
+mod bpm;
+
+type Estimator = fn(&[u64]) -> Result<f64, bpm::BpmCalculationError>;
+const ESTIMATORS: &[(&str, Estimator)] = &[
+    ("direct", bpm::direct_count),
+    ("lin-reg", bpm::simple_regression),
+    ("l1-reg", bpm::least_absolute),
+    ("robust", bpm::robust_regression),
+    ("thiel-sen", bpm::thiel_sen),
+    #[cfg(feature = "tracker")]
+    ("tracker", bpm::alpha_beta),
+    #[cfg(feature = "ransac")]
+    ("ransac", |offsets| Ok(bpm::ransac(offsets, 40_f64, 100)?.bpm)),
+    #[cfg(feature = "repeated-median")]
+    ("rep-median", bpm::repeated_median),
+    #[cfg(feature = "autocorrelation")]
+    ("autocorr", bpm::autocorrelation),
+];
+
+fuzz_target!(|offsets: Vec<u64>| {
+    for (name, estimator) in ESTIMATORS {
+        if let Ok(bpm) = estimator(&offsets) {
+            assert!(bpm.is_finite() && bpm > 0_f64, "{name} gave {bpm}");
+        }
+    }
+});
@@ -0,0 +1,69 @@
+use leptos::prelude::*;
+
+use crate::TapData;
+
+/// Fewest non-overlapping windows an Allan deviation is averaged over
+const MIN_WINDOWS: usize = 3;
+
+/// Allan deviation (ms) of the tap intervals averaged over windows of 1, 2, 4, ... taps
+/// White jitter in the taps averages out so the deviation falls as windows grow, while a
+/// slowly drifting tempo makes it level off or rise
+pub fn allan_deviations(offsets: &[u64]) -> Vec<(usize, f64)> {
+    let intervals: Vec<f64> = offsets.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+    std::iter::successors(Some(1_usize), |m| Some(m * 2))
+        .take_while(|m| intervals.len() / m >= MIN_WINDOWS)
+        .map(|m| {
+            let means: Vec<f64> = intervals
+                .chunks_exact(m)
+                .map(|window| window.iter().sum::<f64>() / m as f64)
+                .collect();
+            let variance = means.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum::<f64>()
+                / (2 * (means.len() - 1)) as f64;
+            (m, variance.sqrt())
+        })
+        .collect()
+}
+
+/// What dominates the timing error, from the log-log slope of the deviation against the
+/// window size: -1 for jitter around a steady beat, -1/2 for a wandering beat, 0 or more
+/// for a drifting one
+fn noise_kind(deviations: &[(usize, f64)]) -> &'static str {
+    let (Some(&(m1, first)), Some(&(m2, last))) = (deviations.first(), deviations.last()) else {
+        return "needs more taps";
+    };
+    if m1 == m2 || first <= 0_f64 || last <= 0_f64 {
+        return "needs more taps";
+    }
+    let slope = (last / first).ln() / (m2 as f64 / m1 as f64).ln();
+    match slope {
+        s if s < -0.75 => "jitter, averages out",
+        s if s < -0.25 => "a wandering beat",
+        _ => "slow drift dominates",
+    }
+}
+
+/// How the timing error changes with the number of taps averaged
+#[component]
+pub fn AllanTable(tap_data: ReadSignal<TapData>) -> impl IntoView {
+    let deviations = Memo::new(move |_| allan_deviations(&tap_data.read().timestamps()));
+
+    view! {
+        "\n"
+        {move || {
+            deviations
+                .get()
+                .into_iter()
+                .map(|(m, deviation)| {
+                    let window = if m == 1 { "1 tap".into() } else { format!("{m} taps") };
+                    view! {
+                        <span class="text-label">{format!("{window:>12}: ")}</span>
+                        <span class="text-value">{format!("{deviation:6.2} ")}</span>
+                        <span class="text-comment">"# ms allan deviation\n"</span>
+                    }
+                })
+                .collect_view()
+        }}
+        <span class="text-label">{format!("{:>12}: ", "noise")}</span>
+        <span class="text-comment">{move || noise_kind(&deviations.read())}"\n"</span>
+    }
+}
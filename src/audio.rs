@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use leptos::logging::error;
+use leptos::prelude::*;
+use leptos_use::{
+    use_display_media_with_options, use_interval_fn_with_options, UseDisplayMediaOptions,
+    UseDisplayMediaReturn, UseIntervalFnOptions,
+};
+use web_sys::{AnalyserNode, AudioContext, MediaStream};
+
+use crate::onset::{OnsetDetector, SpectralFlux};
+
+/// Milliseconds between spectrum reads, roughly one analyser window
+const POLL_MS: u64 = 10;
+const FFT_SIZE: u32 = 1024;
+
+struct Analysis {
+    context: AudioContext,
+    analyser: AnalyserNode,
+    spectrum: Vec<u8>,
+    flux: SpectralFlux,
+    detector: OnsetDetector,
+}
+
+impl Analysis {
+    fn new(stream: &MediaStream) -> Result<Self, wasm_bindgen::JsValue> {
+        let context = AudioContext::new()?;
+        let analyser = context.create_analyser()?;
+        analyser.set_fft_size(FFT_SIZE);
+        context
+            .create_media_stream_source(stream)?
+            .connect_with_audio_node(&analyser)?;
+        Ok(Self {
+            spectrum: vec![0; analyser.frequency_bin_count() as usize],
+            context,
+            analyser,
+            flux: SpectralFlux::default(),
+            detector: OnsetDetector::default(),
+        })
+    }
+}
+
+/// Calls `on_beat` on each onset in audio captured from another tab or the system
+/// `sensitivity` goes from 1 (only the loudest hits) to 9 (most transients);
+/// 0 ends the capture
+pub fn use_display_audio_beats(sensitivity: ReadSignal<u64>, on_beat: impl Fn() + Clone + 'static) {
+    let UseDisplayMediaReturn {
+        stream,
+        start,
+        stop,
+        ..
+    } = use_display_media_with_options(UseDisplayMediaOptions::default().audio(true));
+
+    let analysis = Rc::new(RefCell::new(None::<Analysis>));
+    let poll = {
+        let analysis = Rc::clone(&analysis);
+        use_interval_fn_with_options(
+            move || {
+                let mut analysis = analysis.borrow_mut();
+                let Some(analysis) = analysis.as_mut() else {
+                    return;
+                };
+                analysis
+                    .analyser
+                    .get_byte_frequency_data(&mut analysis.spectrum);
+                let flux = analysis.flux.sample(&analysis.spectrum);
+                let threshold = 1.5 + 0.25 * (10 - sensitivity.get_untracked()) as f64;
+                let now = analysis.context.current_time() * 1_000_f64;
+                if analysis.detector.sample(flux, threshold, now) {
+                    on_beat();
+                }
+            },
+            POLL_MS,
+            UseIntervalFnOptions {
+                immediate: false,
+                immediate_callback: false,
+            },
+        )
+    };
+
+    Effect::new(move |_| {
+        if sensitivity.get() > 0 {
+            start();
+        } else {
+            stop();
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(previous) = analysis.take() {
+            let _ = previous.context.close();
+        }
+        match stream.get() {
+            Some(Ok(stream)) if stream.get_audio_tracks().length() == 0 => {
+                error!("the captured surface has no audio, try sharing a tab");
+            }
+            Some(Ok(stream)) => match Analysis::new(&stream) {
+                Ok(new) => {
+                    analysis.replace(Some(new));
+                    (poll.resume)();
+                }
+                Err(e) => error!("audio analysis unavailable: {e:?}"),
+            },
+            Some(Err(e)) => error!("display capture unavailable: {e:?}"),
+            None => (poll.pause)(),
+        }
+    });
+}
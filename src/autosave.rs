@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use leptos::ev::pagehide;
+use leptos::prelude::*;
+use leptos_use::{use_event_listener, use_throttle_fn, use_window};
+use serde::Serialize;
+use web_sys::Storage;
+
+use crate::bpm::thiel_sen;
+use crate::{export, schema, TapData};
+
+/// Every key the app saves under starts with this, the origin is shared with other pages
+const KEY_PREFIX: &str = "bpm-";
+const KEY: &str = "bpm-session";
+/// Sessions are saved at most this often while tapping, in milliseconds
+const SAVE_INTERVAL_MS: f64 = 1000.0;
+
+pub fn storage() -> Option<Storage> {
+    window().local_storage().ok().flatten()
+}
+
+/// Everything the app has saved in this browser, as exported before a wipe or a move
+#[derive(Serialize)]
+struct Archive {
+    local_storage: BTreeMap<String, String>,
+}
+
+/// The keys in `storage` that belong to the app, leaving those of other pages on the origin
+fn own_keys(storage: &Storage) -> Vec<String> {
+    let length = storage.length().unwrap_or_default();
+    (0..length)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .filter(|key| key.starts_with(KEY_PREFIX))
+        .collect()
+}
+
+fn archive(storage: &Storage) -> Archive {
+    let local_storage = own_keys(storage)
+        .into_iter()
+        .filter_map(|key| Some((key.clone(), storage.get_item(&key).ok().flatten()?)))
+        .collect();
+    Archive { local_storage }
+}
+
+/// What's saved of `taps`, nothing while a fresh page or a stray tap would clobber the last
+/// session
+fn encode(taps: &TapData) -> Option<String> {
+    (taps.len() >= 2).then(|| schema::to_json(taps))
+}
+
+fn decode(saved: &str) -> Option<Vec<u64>> {
+    let taps = schema::from_json::<TapData>(saved).ok()?;
+    Some(taps.timestamps())
+}
+
+/// Keeps the session in localStorage as it is tapped, and returns the one saved by the
+/// previous visit if it had enough taps to be worth restoring
+pub fn use_autosave(tap_data: ReadSignal<TapData>) -> Option<Vec<u64>> {
+    let saved = storage()
+        .and_then(|storage| storage.get_item(KEY).ok().flatten())
+        .and_then(|saved| decode(&saved))
+        .filter(|timestamps| timestamps.len() >= 2);
+
+    let save = move || {
+        if let (Some(storage), Some(saved)) = (storage(), tap_data.with_untracked(encode)) {
+            let _ = storage.set_item(KEY, &saved);
+        }
+    };
+    let throttled_save = use_throttle_fn(save, SAVE_INTERVAL_MS);
+    Effect::new(move |_| {
+        tap_data.track();
+        throttled_save();
+    });
+    // the throttle may still be waiting when the tab is closed
+    let _cleanup = use_event_listener(use_window(), pagehide, move |_| save());
+
+    saved
+}
+
+#[component]
+pub fn RestoreOffer(saved: Vec<u64>, set_tap_data: WriteSignal<TapData>) -> impl IntoView {
+    let (offer, set_offer) = signal(Some(saved));
+    let summary = move || {
+        offer.with(|saved| {
+            let saved = saved.as_deref().unwrap_or_default();
+            match thiel_sen(saved) {
+                Ok(bpm) => format!("{} taps, {bpm:.2} bpm", saved.len()),
+                Err(_) => format!("{} taps", saved.len()),
+            }
+        })
+    };
+    let restore = move |_| {
+        if let Some(saved) = set_offer.write().take() {
+            set_tap_data.write().load(saved);
+        }
+    };
+
+    view! {
+        <Show when=move || offer.read().is_some()>
+            <span class="text-label">"     restore:  "</span>
+            <button class="hover:text-value" on:mousedown=restore>
+                "yes"
+            </button>
+            " "
+            <button class="hover:text-value" on:mousedown=move |_| set_offer.set(None)>
+                "no"
+            </button>
+            <span class="text-comment">" # "{summary}"\n"</span>
+        </Show>
+    }
+}
+
+/// Exports or wipes everything saved, wiping the live session with it so it isn't saved again
+#[component]
+pub fn StoredData(
+    reset: impl Fn() + Copy + Send + Sync + 'static,
+    set_tap_data: WriteSignal<TapData>,
+) -> impl IntoView {
+    let (armed, set_armed) = signal(false);
+    let export = move |_| {
+        if let Some(storage) = storage() {
+            let json = schema::to_json(&archive(&storage));
+            export::download_text("bpm-data.json", "application/json", &json);
+        }
+    };
+    // wiping takes a second click, in case the first was a slip
+    let clear = move |_| {
+        if !armed.get() {
+            set_armed.set(true);
+            return;
+        }
+        reset();
+        // a reset keeps the taps for joining, which autosave would write straight back
+        set_tap_data.write().load(Vec::new());
+        if let Some(storage) = storage() {
+            for key in own_keys(&storage) {
+                let _ = storage.remove_item(&key);
+            }
+        }
+        set_armed.set(false);
+    };
+
+    view! {
+        <span class="text-label">"        data:  "</span>
+        <button class="hover:text-value" on:mousedown=export>
+            "export"
+        </button>
+        " "
+        <button class="hover:text-value" on:mousedown=clear>
+            {move || if armed.get() { "sure?" } else { "clear" }}
+        </button>
+        <span class="text-comment">
+            {move || {
+                if armed.get() {
+                    " # clears this session too\n"
+                } else {
+                    " # everything the app saved in this browser\n"
+                }
+            }}
+        </span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_nothing_once_cleared() {
+        let mut taps = TapData::default();
+        taps.load(vec![0, 500, 1000]);
+        let saved = encode(&taps).unwrap();
+        assert_eq!(decode(&saved), Some(vec![0, 500, 1000]));
+        taps.load(Vec::new());
+        assert_eq!(encode(&taps), None);
+    }
+}
@@ -0,0 +1,44 @@
+use leptos::prelude::*;
+
+use crate::TapData;
+
+/// Bar and beat of tap number `taps`, all counted from 1, if there is a tap and a bar
+fn bar_beat(taps: usize, beats_per_bar: usize) -> Option<(usize, usize)> {
+    let beat = taps.checked_sub(1)?;
+    Some((
+        beat.checked_div(beats_per_bar)? + 1,
+        beat.checked_rem(beats_per_bar)? + 1,
+    ))
+}
+
+/// Counts taps off in bars, for keeping track of 16 and 32 bar phrases
+#[component]
+pub fn BarCounter(tap_data: ReadSignal<TapData>, beats_per_bar: ReadSignal<u64>) -> impl IntoView {
+    view! {
+        <span class="text-label">{format!("{:>12}: ", "bar:beat")}</span>
+        <span class="text-value">
+            // the setting can be 0 for a moment before the count is hidden
+            {move || match bar_beat(tap_data.read().len(), beats_per_bar.get() as usize) {
+                Some((bar, beat)) => format!("{:>6} ", format!("{bar}:{beat}")),
+                None => "   -:- ".into(),
+            }}
+        </span>
+        <span class="text-comment">
+            "# "{move || beats_per_bar.get()}" beats a bar, by tap\n"
+        </span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_bars() {
+        assert_eq!(bar_beat(1, 4), Some((1, 1)));
+        assert_eq!(bar_beat(4, 4), Some((1, 4)));
+        assert_eq!(bar_beat(5, 4), Some((2, 1)));
+        assert_eq!(bar_beat(0, 4), None);
+        assert_eq!(bar_beat(5, 0), None);
+    }
+}
@@ -1,16 +1,70 @@
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BpmCalculationError {
     #[error("not enough data in input vector")]
     InsufficientData,
+    #[error("weights don't match the offsets one to one")]
+    MismatchedWeights,
+    #[error("taps are out of order")]
+    Unordered,
+    #[error("taps span no time")]
+    NoDuration,
+    #[error("estimate isn't a finite number")]
+    NotFinite,
+    #[error("over {0}, double taps or subdivisions?")]
+    Implausible(u64),
 }
 
-pub fn direct_count(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+/// Residuals this many scaled MADs off the grid get half weight in `robust_regression`
+const CAUCHY_SCALE: f64 = 2.385;
+/// Smallest residual scale (ms), so near perfect taps don't make every error look huge
+const MIN_SCALE_MS: f64 = 1.0;
+
+/// Most reweightings `least_absolute` makes, and the relative change of slope it stops at
+const L1_ITERATIONS: usize = 50;
+const L1_TOLERANCE: f64 = 1e-9;
+/// Smallest distance (ms) `least_absolute` weighs by, far below the clock's resolution so the
+/// taps the line passes through hold it there
+const L1_MIN_MS: f64 = 0.001;
+
+/// Checks that `offsets` are at least two taps, in order, that don't all land at once
+fn check_taps(offsets: &[u64]) -> Result<(), BpmCalculationError> {
     if offsets.len() < 2 {
         return Err(BpmCalculationError::InsufficientData);
     }
+    if !offsets.is_sorted() {
+        return Err(BpmCalculationError::Unordered);
+    }
+    if offsets[0] == offsets[offsets.len() - 1] {
+        return Err(BpmCalculationError::NoDuration);
+    }
+    Ok(())
+}
+
+/// Passes `bpm` through if it is a number that can be shown, so NaN and infinities from
+/// any estimator become an error instead
+pub fn finite(bpm: f64) -> Result<f64, BpmCalculationError> {
+    if bpm.is_finite() {
+        Ok(bpm)
+    } else {
+        Err(BpmCalculationError::NotFinite)
+    }
+}
+
+/// Passes `bpm` on if it's at most `max`, which is no bound when 0
+pub fn plausible(bpm: f64, max: u64) -> Result<f64, BpmCalculationError> {
+    if max == 0 || bpm <= max as f64 {
+        Ok(bpm)
+    } else {
+        Err(BpmCalculationError::Implausible(max))
+    }
+}
+
+pub fn direct_count(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+    check_taps(offsets)?;
 
     let start = offsets[0];
     let end = offsets.last().unwrap();
@@ -25,45 +79,792 @@ pub fn direct_count(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
 pub fn simple_regression(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
     // Slope of least squares regression line is equal to Cov(x, y) / Var(x)
     // https://seismo.berkeley.edu/~kirchner/eps_120/Toolkits/Toolkit_10.pdf
-    if offsets.len() < 2 {
+    check_taps(offsets)?;
+
+    // measured from the first tap, so the offsets convert to f64 exactly
+    let xs = || offsets.iter().map(|&x| (x - offsets[0]) as f64);
+    let n = offsets.len() as f64;
+    let mean_x = xs().sum::<f64>() / n;
+    let mean_y = (n - 1_f64) / 2_f64;
+
+    // summing deviations from the means, since raw sums of squares overflow on long sessions
+    let (covariance, variance) = xs().enumerate().fold((0_f64, 0_f64), |(cov, var), (y, x)| {
+        let dx = x - mean_x;
+        (cov + dx * (y as f64 - mean_y), var + dx * dx)
+    });
+
+    let slope = covariance / variance; // beats per millisecond
+    Ok(slope * 60_000_f64)
+}
+
+/// Least squares where each tap counts in proportion to its weight, so suspect taps can be
+/// trusted less without being thrown out
+pub fn weighted_regression(offsets: &[u64], weights: &[f64]) -> Result<f64, BpmCalculationError> {
+    Ok(weighted_fit(offsets, weights)?.bpm())
+}
+
+/// The beat grid `weighted_regression` fits
+fn weighted_fit(offsets: &[u64], weights: &[f64]) -> Result<LineFit, BpmCalculationError> {
+    if weights.len() != offsets.len() {
+        return Err(BpmCalculationError::MismatchedWeights);
+    }
+    check_taps(offsets)?;
+    let total: f64 = weights.iter().sum();
+    if total <= 0_f64 {
         return Err(BpmCalculationError::InsufficientData);
     }
 
-    let (sum_x, sum_x_squared, sum_xy) = offsets
+    // measured from the first tap, so the offsets convert to f64 exactly
+    let xs = offsets.iter().map(|&x| (x - offsets[0]) as f64);
+    let points = || xs.clone().zip(weights).enumerate();
+    let mean_x = points().map(|(_, (x, w))| w * x).sum::<f64>() / total;
+    let mean_y = points().map(|(y, (_, w))| w * y as f64).sum::<f64>() / total;
+    let (covariance, variance) = points().fold((0_f64, 0_f64), |(cov, var), (y, (x, w))| {
+        let dx = x - mean_x;
+        (cov + w * dx * (y as f64 - mean_y), var + w * dx * dx)
+    });
+    // all the weight is on taps at the same time
+    if variance <= 0_f64 {
+        return Err(BpmCalculationError::NoDuration);
+    }
+
+    let slope = covariance / variance;
+    Ok(LineFit {
+        slope,
+        intercept: mean_y - slope * (mean_x + offsets[0] as f64),
+    })
+}
+
+/// Least absolute deviations, the line that minimises the summed distance of the taps from
+/// it rather than the squared one, so a bad tap pulls it less than lin-reg but more than
+/// thiel-sen
+/// Found by iteratively reweighted least squares, each tap weighted by one over its last
+/// distance from the line
+pub fn least_absolute(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+    check_taps(offsets)?;
+    let mut fit = weighted_fit(offsets, &vec![1_f64; offsets.len()])?;
+    for _ in 0..L1_ITERATIONS {
+        let weights: Vec<f64> = offsets
+            .iter()
+            .enumerate()
+            .map(|(y, &x)| 1_f64 / (x as f64 - fit.offset_of(y as f64)).abs().max(L1_MIN_MS))
+            .collect();
+        let refit = weighted_fit(offsets, &weights)?;
+        let settled = (refit.slope - fit.slope).abs() <= L1_TOLERANCE * fit.slope.abs();
+        fit = refit;
+        if settled {
+            break;
+        }
+    }
+    Ok(fit.bpm())
+}
+
+/// Weighted regression where a tap's weight halves every `half_life_ms` before the last tap,
+/// so a change of tempo shows up sooner than in `simple_regression`
+pub fn recency_regression(offsets: &[u64], half_life_ms: f64) -> Result<f64, BpmCalculationError> {
+    check_taps(offsets)?;
+    let last = offsets[offsets.len() - 1];
+    let weights: Vec<f64> = offsets
         .iter()
-        .enumerate()
-        .fold((0_u64, 0_u64, 0_u64), |(sx, sxx, sxy), (y, x)| {
-            (sx + x, sxx + x * x, sxy + (y as u64) * x)
-        });
+        .map(|&x| 0.5_f64.powf((last - x) as f64 / half_life_ms))
+        .collect();
+    weighted_regression(offsets, &weights)
+}
 
-    let n = offsets.len() as f64;
-    let mean_x = sum_x as f64 / n;
-    let mean_y = (n - 1_f64) / 2_f64;
+/// Weighted regression with weights from each tap's distance to the thiel-sen grid
+pub fn robust_regression(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+    weighted_regression(offsets, &robust_weights(offsets)?)
+}
 
-    let slope = // beats per millisecond
-        (sum_xy as f64 - n * mean_x * mean_y) /
-        (sum_x_squared as f64 - n * mean_x * mean_x);
+/// The weight `robust_regression` gives each tap, falling off with its distance to the grid
+pub fn robust_weights(offsets: &[u64]) -> Result<Vec<f64>, BpmCalculationError> {
+    let fit = thiel_sen_fit(offsets)?;
+    let errors: Vec<f64> = offsets
+        .iter()
+        .enumerate()
+        .map(|(y, &x)| x as f64 - fit.offset_of(y as f64))
+        .collect();
+    // 1.4826 scales the MAD to a standard deviation for normally distributed errors
+    let scale = (1.4826 * median(errors.iter().map(|e| e.abs()).collect())).max(MIN_SCALE_MS);
+    Ok(errors
+        .iter()
+        .map(|e| 1_f64 / (1_f64 + (e / (CAUCHY_SCALE * scale)).powi(2)))
+        .collect())
+}
 
-    Ok(slope * 60_000_f64)
+/// How many equally weighted taps `weights` are worth (Kish's effective sample size)
+pub fn effective_taps(weights: &[f64]) -> f64 {
+    let total: f64 = weights.iter().sum();
+    let squares: f64 = weights.iter().map(|w| w * w).sum();
+    if squares > 0_f64 {
+        total * total / squares
+    } else {
+        0_f64
+    }
 }
 
 pub fn thiel_sen(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+    Ok(thiel_sen_fit(offsets)?.bpm())
+}
+
+/// A beat grid fit to the taps, with offsets (ms) on x and beat numbers on y
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LineFit {
+    /// Beats per millisecond
+    pub slope: f64,
+    /// Beat number at offset 0
+    pub intercept: f64,
+}
+
+impl LineFit {
+    pub fn bpm(&self) -> f64 {
+        self.slope * 60_000_f64
+    }
+
+    /// Beat number the grid is on at `offset` (ms), the fraction being how far into the beat
+    pub fn beat_at(&self, offset: f64) -> f64 {
+        self.slope * offset + self.intercept
+    }
+
+    /// Offset (ms) at which `beat` falls on the grid
+    pub fn offset_of(&self, beat: f64) -> f64 {
+        (beat - self.intercept) / self.slope
+    }
+}
+
+pub fn thiel_sen_fit(offsets: &[u64]) -> Result<LineFit, BpmCalculationError> {
     // The median of the slopes between every pair of points
     // Increased robustness, asymptotic efficiency (data required to converge)
     // https://en.wikipedia.org/wiki/Theil%E2%80%93Sen_estimator
-    if offsets.len() < 2 {
-        return Err(BpmCalculationError::InsufficientData);
+    check_taps(offsets)?;
+
+    // taps at the same time have no slope between them, and the first and last taps always do
+    let slopes = pairwise_slopes(offsets)
+        .into_iter()
+        .filter(|s| s.is_finite());
+    let slope = median(slopes.collect());
+    // the matching intercept is the median of y - m*x over the taps
+    let intercept = median(
+        offsets
+            .iter()
+            .enumerate()
+            .map(|(y, &x)| y as f64 - slope * x as f64)
+            .collect(),
+    );
+    Ok(LineFit { slope, intercept })
+}
+
+/// Siegel's repeated median: each tap's median slope to every other tap, and the median of
+/// those, which holds up with nearly half the taps bad where thiel-sen gives out at 29%
+#[cfg(feature = "repeated-median")]
+pub fn repeated_median(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+    check_taps(offsets)?;
+    // measured from the first tap, so the offsets convert to f64 exactly
+    let xs: Vec<f64> = offsets.iter().map(|&x| (x - offsets[0]) as f64).collect();
+    let slope_from = |(y1, &x1): (usize, &f64)| {
+        let slopes: Vec<f64> = xs
+            .iter()
+            .enumerate()
+            .filter(|&(y2, _)| y2 != y1)
+            .map(|(y2, &x2)| (y2 as f64 - y1 as f64) / (x2 - x1))
+            // taps at the same time have no slope between them
+            .filter(|s| s.is_finite())
+            .collect();
+        (!slopes.is_empty()).then(|| median(slopes))
+    };
+    let medians = xs.iter().enumerate().filter_map(slope_from).collect();
+    Ok(median(medians) * 60_000_f64)
+}
+
+/// A tempo fit to the taps that agree on it, and how many did
+#[cfg(feature = "ransac")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Consensus {
+    pub bpm: f64,
+    pub inliers: usize,
+}
+
+/// RANSAC: of up to `iterations` lines through two taps, the one the most taps land within
+/// `threshold_ms` of, refit by least squares over just those taps
+/// Every pair is tried when there are no more than `iterations` of them, and otherwise
+/// pairs are drawn from a fixed seed, so the same taps always give the same estimate
+#[cfg(feature = "ransac")]
+pub fn ransac(
+    offsets: &[u64],
+    threshold_ms: f64,
+    iterations: usize,
+) -> Result<Consensus, BpmCalculationError> {
+    check_taps(offsets)?;
+    let n = offsets.len();
+    // measured from the first tap, so the offsets convert to f64 exactly
+    let xs: Vec<f64> = offsets.iter().map(|&x| (x - offsets[0]) as f64).collect();
+    let mut seed = 0x9E37_79B9_7F4A_7C15_u64;
+    let mut draw = move || {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let (i, j) = ((seed >> 33) as usize % n, (seed >> 11) as usize % n);
+        (i.min(j), i.max(j))
+    };
+    // every pair is tried if there are few enough, which on wasm's 32 bit usize can
+    // overflow counting for a long session
+    let every_pair = n
+        .checked_mul(n - 1)
+        .is_some_and(|twice| twice / 2 <= iterations);
+    let pairs: Vec<(usize, usize)> = if every_pair {
+        (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect()
+    } else {
+        (0..iterations).map(|_| draw()).collect()
+    };
+
+    // the most inliers wins, and of those the line they sit closest to
+    let mut best: Option<(usize, f64, Vec<f64>)> = None;
+    for (i, j) in pairs {
+        // taps at the same time have no line through them
+        if xs[i] == xs[j] {
+            continue;
+        }
+        let fit = LineFit {
+            slope: (j - i) as f64 / (xs[j] - xs[i]),
+            intercept: i as f64 - (j - i) as f64 / (xs[j] - xs[i]) * xs[i],
+        };
+        let errors = xs
+            .iter()
+            .enumerate()
+            .map(|(y, x)| (x - fit.offset_of(y as f64)).abs());
+        let weights: Vec<f64> = errors
+            .clone()
+            .map(|e| if e <= threshold_ms { 1_f64 } else { 0_f64 })
+            .collect();
+        let inliers = weights.iter().filter(|&&w| w > 0_f64).count();
+        let error: f64 = errors.filter(|&e| e <= threshold_ms).sum();
+        if best
+            .as_ref()
+            .is_none_or(|&(most, least, _)| inliers > most || inliers == most && error < least)
+        {
+            best = Some((inliers, error, weights));
+        }
     }
 
-    let mut slopes: Vec<_> = offsets
+    let (inliers, _, weights) = best.ok_or(BpmCalculationError::NoDuration)?;
+    Ok(Consensus {
+        bpm: weighted_regression(offsets, &weights)?,
+        inliers,
+    })
+}
+
+// the comb filter is the heaviest estimator, and can be left out of the build
+#[cfg(feature = "autocorrelation")]
+pub use comb::autocorrelation;
+// so can the tracker
+#[cfg(feature = "tracker")]
+pub use tracker::alpha_beta;
+
+// wasm can't detect features at runtime, so builds with simd128 enabled use the simd path
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub use simd::pairwise_slopes;
+
+/// Slopes (beats per millisecond) between every pair of taps, in pair order
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+pub fn pairwise_slopes(offsets: &[u64]) -> Vec<f64> {
+    offsets
         .iter()
         .enumerate()
         .tuple_combinations()
         // indices (number of beats) are the y-values
         .map(|((y1, x1), (y2, x2))| (y2 - y1) as f64 / (x2 - x1) as f64)
-        .collect();
-    let mid = slopes.len() / 2;
-    let (_left, median, _right) = slopes.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+        .collect()
+}
+
+/// The upper median of `values`, which must not be empty
+pub fn median(mut values: Vec<f64>) -> f64 {
+    let mid = values.len() / 2;
+    let (_left, median, _right) = values.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+    *median
+}
+
+/// The last `n` of `offsets`, so estimates made from them follow the recent tempo; 0 keeps
+/// every tap
+pub fn windowed(offsets: &[u64], n: usize) -> &[u64] {
+    match n {
+        0 => offsets,
+        n => &offsets[offsets.len().saturating_sub(n)..],
+    }
+}
+
+/// Drops taps that come sooner than `fraction` of the median interval after the tap before
+/// them, merging probable double taps into their first tap
+pub fn merge_double_taps(offsets: &[u64], fraction: f64) -> Vec<u64> {
+    if offsets.len() < 3 {
+        return offsets.to_vec();
+    }
+    let intervals = offsets.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+    let min_interval = fraction * median(intervals);
+    let mut merged = vec![offsets[0]];
+    for &offset in &offsets[1..] {
+        if (offset - merged[merged.len() - 1]) as f64 >= min_interval {
+            merged.push(offset);
+        }
+    }
+    merged
+}
+
+#[cfg(feature = "tracker")]
+mod tracker {
+    use super::{check_taps, BpmCalculationError};
+
+    /// Share of each tap's surprise `alpha_beta` moves its phase and period by, critically
+    /// damped so the tracker settles without ringing
+    const TRACK_ALPHA: f64 = 0.4;
+    const TRACK_BETA: f64 = TRACK_ALPHA * TRACK_ALPHA / (2_f64 - TRACK_ALPHA);
+    /// Shortest beat period (ms) the tracker allows, keeping its estimate finite and positive
+    const MIN_TRACK_PERIOD_MS: f64 = 1.0;
+
+    /// Tempo from an alpha-beta filter, the steady state of a kalman filter, tracking the beat's
+    /// phase and period tap by tap
+    /// Each tap corrects the predicted one by a share of how far off it landed, so the tracker
+    /// settles within a few taps and follows a drifting tempo that the regressions lag behind
+    pub fn alpha_beta(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+        check_taps(offsets)?;
+        let mut phase = offsets[1] as f64;
+        let mut period = (offsets[1] - offsets[0]) as f64;
+        for &offset in &offsets[2..] {
+            let predicted = phase + period;
+            let surprise = offset as f64 - predicted;
+            phase = predicted + TRACK_ALPHA * surprise;
+            period = (period + TRACK_BETA * surprise).max(MIN_TRACK_PERIOD_MS);
+        }
+        Ok(60_000_f64 / period.max(MIN_TRACK_PERIOD_MS))
+    }
+}
+
+#[cfg(feature = "autocorrelation")]
+mod comb {
+    use itertools::Itertools;
+
+    use super::{check_taps, BpmCalculationError};
+
+    /// Beat periods (ms) `autocorrelation` looks for, 300 down to 40 bpm
+    const MIN_PERIOD_MS: usize = 200;
+    const MAX_PERIOD_MS: usize = 1500;
+    /// Multiples of each period the comb filter checks
+    const COMB_TEETH: usize = 4;
+    /// Intervals longer than this are left out of the autocorrelation
+    const MAX_LAG_MS: usize = MAX_PERIOD_MS * COMB_TEETH;
+    /// Spread of the kernel each interval is smoothed with, absorbing tap jitter
+    const LAG_JITTER_MS: f64 = 10.0;
+
+    /// Tempo with the strongest periodicity in the tap train
+    /// Each interval between any two taps (up to `MAX_LAG_MS`) votes for a lag, and every
+    /// candidate period is scored by how many votes land on its first few multiples, a comb
+    /// filter over the autocorrelation, so missed beats still count towards the right tempo
+    pub fn autocorrelation(offsets: &[u64]) -> Result<f64, BpmCalculationError> {
+        check_taps(offsets)?;
+
+        let intervals: Vec<usize> = offsets
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| b.abs_diff(*a) as usize)
+            .filter(|&lag| (1..=MAX_LAG_MS).contains(&lag))
+            .collect();
+        let spread = (3_f64 * LAG_JITTER_MS) as usize;
+        let mut votes = vec![0_f64; MAX_LAG_MS + spread + 1];
+        for &lag in &intervals {
+            let first = lag.saturating_sub(spread);
+            for (bin, vote) in (first..).zip(&mut votes[first..=lag + spread]) {
+                let error = (bin as f64 - lag as f64) / LAG_JITTER_MS;
+                *vote += (-error * error / 2_f64).exp();
+            }
+        }
+
+        // a fixed number of teeth keeps multiples of the beat from tying with it, since there
+        // are fewer long intervals than short ones, and subdivisions miss every other tooth
+        let comb = |period: usize| (1..=COMB_TEETH).map(|k| votes[k * period]).sum::<f64>();
+        let period = (MIN_PERIOD_MS..=MAX_PERIOD_MS)
+            .max_by(|&a, &b| comb(a).total_cmp(&comb(b)))
+            .filter(|&period| comb(period) > 0_f64)
+            .ok_or(BpmCalculationError::InsufficientData)?;
+
+        // refine the whole millisecond period with the intervals that voted for it
+        let (total, beats) = intervals
+            .iter()
+            .map(|&lag| (lag as f64, (lag as f64 / period as f64).round()))
+            .filter(|&(lag, k)| {
+                k >= 1_f64 && (lag - k * period as f64).abs() <= 2_f64 * LAG_JITTER_MS
+            })
+            .fold((0_f64, 0_f64), |(total, beats), (lag, k)| {
+                (total + lag, beats + k)
+            });
+        // the kernel reaches a little past the refinement window, so the winner can be empty
+        if beats == 0_f64 {
+            return Err(BpmCalculationError::InsufficientData);
+        }
+
+        Ok(beats * 60_000_f64 / total)
+    }
+}
+
+/// WASM SIMD version of `pairwise_slopes`, dividing two pairs at once
+/// Offsets are whole milliseconds far below 2^53, so the differences are exact in f64 and
+/// the slopes match the scalar path bit for bit
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod simd {
+    use core::arch::wasm32::{f64x2, f64x2_div, f64x2_extract_lane, f64x2_splat, f64x2_sub};
+
+    pub fn pairwise_slopes(offsets: &[u64]) -> Vec<f64> {
+        let xs: Vec<f64> = offsets.iter().map(|&x| x as f64).collect();
+        let n = xs.len();
+        let mut slopes = Vec::with_capacity(n * n.saturating_sub(1) / 2);
+        for (i, &x1) in xs.iter().enumerate() {
+            let (x1, y1) = (f64x2_splat(x1), f64x2_splat(i as f64));
+            let mut j = i + 1;
+            while j + 1 < n {
+                let dy = f64x2_sub(f64x2(j as f64, (j + 1) as f64), y1);
+                let dx = f64x2_sub(f64x2(xs[j], xs[j + 1]), x1);
+                let slope = f64x2_div(dy, dx);
+                slopes.push(f64x2_extract_lane::<0>(slope));
+                slopes.push(f64x2_extract_lane::<1>(slope));
+                j += 2;
+            }
+            if j < n {
+                slopes.push((j - i) as f64 / (xs[j] - xs[i]));
+            }
+        }
+        slopes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Taps on a 120 bpm grid
+    const STEADY: [u64; 4] = [0, 500, 1000, 1500];
+    /// A grid at 120 bpm with the second tap 100ms late
+    const LATE: [u64; 4] = [0, 600, 1000, 1500];
+
+    type Estimator = fn(&[u64]) -> Result<f64, BpmCalculationError>;
+    const ESTIMATORS: &[(&str, Estimator)] = &[
+        ("direct", direct_count),
+        ("lin-reg", simple_regression),
+        ("l1-reg", least_absolute),
+        ("robust", robust_regression),
+        ("thiel-sen", thiel_sen),
+        #[cfg(feature = "tracker")]
+        ("tracker", alpha_beta),
+        #[cfg(feature = "ransac")]
+        ("ransac", |offsets| Ok(ransac(offsets, 40_f64, 100)?.bpm)),
+        #[cfg(feature = "repeated-median")]
+        ("rep-median", repeated_median),
+        #[cfg(feature = "autocorrelation")]
+        ("autocorr", autocorrelation),
+    ];
+
+    fn assert_bpm(actual: Result<f64, BpmCalculationError>, expected: f64) {
+        let actual = actual.expect("estimate");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn steady_taps() {
+        for (name, estimator) in ESTIMATORS {
+            let bpm = estimator(&STEADY).expect(name);
+            assert!((bpm - 120_f64).abs() < 1e-9, "{name} gave {bpm}");
+        }
+    }
+
+    #[test]
+    fn two_taps() {
+        for (name, estimator) in ESTIMATORS {
+            let bpm = estimator(&[250, 750]).expect(name);
+            assert!((bpm - 120_f64).abs() < 1e-9, "{name} gave {bpm}");
+        }
+    }
+
+    #[test]
+    fn too_few_taps() {
+        for (name, estimator) in ESTIMATORS {
+            for offsets in [&[][..], &[1000]] {
+                assert!(
+                    matches!(
+                        estimator(offsets),
+                        Err(BpmCalculationError::InsufficientData)
+                    ),
+                    "{name} estimated {offsets:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn late_tap() {
+        // the count only sees the ends and thiel-sen outvotes the late tap
+        assert_bpm(direct_count(&LATE), 120_f64);
+        // covariance 2450 over variance 1207500, times 60000
+        assert_bpm(simple_regression(&LATE), 2800_f64 / 23_f64);
+        // slopes 1/600, 1/500 (three times), 1/450 and 1/400, with the upper median 1/500
+        assert_bpm(thiel_sen(&LATE), 120_f64);
+        // the late tap is 100ms off the grid with the other residuals 0, so it gets weight
+        // 1 / (1 + (100 / 2.385)^2) and the other three lie on the grid
+        assert_bpm(robust_regression(&LATE), 120.00136410321302);
+        // which leaves about three taps' worth
+        let used = effective_taps(&robust_weights(&LATE).unwrap());
+        assert_eq!(used.round(), 3_f64);
+        assert_eq!(effective_taps(&[0.5; 4]), 4_f64);
+        // the summed distance is least through the three taps on the grid
+        let l1 = least_absolute(&LATE).unwrap();
+        assert!((l1 - 120_f64).abs() < 0.01, "l1-reg gave {l1}");
+    }
 
-    Ok(*median * 60_000_f64)
+    #[test]
+    #[cfg(feature = "ransac")]
+    fn flubbed_taps() {
+        // a 120 bpm grid with two taps badly off, which the consensus leaves out
+        let offsets = [0, 500, 1000, 1300, 2000, 2500, 2820, 3500];
+        let consensus = ransac(&offsets, 40_f64, 100).unwrap();
+        assert_bpm(Ok(consensus.bpm), 120_f64);
+        assert_eq!(consensus.inliers, 6);
+        // a wide enough threshold takes every tap in, like lin-reg
+        let consensus = ransac(&offsets, 400_f64, 100).unwrap();
+        assert_eq!(consensus.inliers, 8);
+        assert_bpm(Ok(consensus.bpm), simple_regression(&offsets).unwrap());
+        // too few iterations to try every pair still finds the grid
+        assert_eq!(ransac(&offsets, 40_f64, 20).unwrap().inliers, 6);
+    }
+
+    #[test]
+    #[cfg(feature = "autocorrelation")]
+    fn jittered_taps() {
+        // every pair votes for the 500ms period, adding up to 20 beats over 9980ms
+        assert_bpm(
+            autocorrelation(&[0, 505, 1000, 1495, 2000]),
+            60_000_f64 / 499_f64,
+        );
+    }
+
+    #[test]
+    fn missed_beat() {
+        let offsets = [0, 500, 1000, 2000, 2500];
+        assert_bpm(direct_count(&offsets), 96_f64);
+        #[cfg(feature = "autocorrelation")]
+        assert_bpm(autocorrelation(&offsets), 120_f64);
+    }
+
+    #[test]
+    fn weighted_matches_simple() {
+        assert_bpm(weighted_regression(&LATE, &[1_f64; 4]), 2800_f64 / 23_f64);
+        // without the late tap the rest lie on the grid
+        assert_bpm(
+            weighted_regression(&LATE, &[1_f64, 0_f64, 1_f64, 1_f64]),
+            120_f64,
+        );
+        assert!(matches!(
+            weighted_regression(&LATE, &[1_f64; 3]),
+            Err(BpmCalculationError::MismatchedWeights)
+        ));
+        assert!(matches!(
+            weighted_regression(&LATE, &[0_f64; 4]),
+            Err(BpmCalculationError::InsufficientData)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "repeated-median")]
+    fn repeated_median_outlasts_thiel_sen() {
+        // four of ten taps are 150 to 240ms off a 120 bpm grid
+        let offsets = [240, 650, 1150, 1500, 2150, 2500, 3000, 3500, 4000, 4500];
+        assert_bpm(repeated_median(&offsets), 120_f64);
+        assert!((thiel_sen(&offsets).unwrap() - 120_f64).abs() > 5_f64);
+    }
+
+    #[test]
+    #[cfg(feature = "tracker")]
+    fn tracker_follows_drift() {
+        // ten beats at 120 bpm, then ten at 150
+        let offsets: Vec<u64> = (0..=10)
+            .map(|beat| beat * 500)
+            .chain((1..=10).map(|beat| 5000 + beat * 400))
+            .collect();
+        let tracked = alpha_beta(&offsets).unwrap();
+        assert!((tracked - 150_f64).abs() < 2_f64, "got {tracked}");
+        assert!(tracked > simple_regression(&offsets).unwrap());
+        // taps at once don't leave the period at 0
+        assert!(alpha_beta(&[0, 0, 1000]).unwrap().is_finite());
+    }
+
+    #[test]
+    fn windows_the_last_taps() {
+        let offsets = [0, 500, 1000, 1400, 1800];
+        assert_eq!(windowed(&offsets, 3), &[1000, 1400, 1800]);
+        assert_eq!(windowed(&offsets, 0), &offsets);
+        assert_eq!(windowed(&offsets, 9), &offsets);
+        assert_bpm(thiel_sen(windowed(&offsets, 3)), 150_f64);
+    }
+
+    #[test]
+    fn recency_follows_a_tempo_change() {
+        // ten beats at 120 bpm, then ten at 150
+        let offsets: Vec<u64> = (0..=10)
+            .map(|beat| beat * 500)
+            .chain((1..=10).map(|beat| 5000 + beat * 400))
+            .collect();
+        let recent = recency_regression(&offsets, 1000_f64).unwrap();
+        assert!(recent > 145_f64, "got {recent}");
+        assert!(recent > simple_regression(&offsets).unwrap());
+        // a long half-life weighs every tap about the same
+        let flat = recency_regression(&offsets, 1e12).unwrap();
+        assert!((flat - simple_regression(&offsets).unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn identical_timestamps() {
+        // a double tap in the middle of the session
+        let offsets = [0, 500, 500, 1000];
+        assert_bpm(direct_count(&offsets), 180_f64);
+        // covariance 1500 over variance 500000, times 60000
+        assert_bpm(simple_regression(&offsets), 180_f64);
+        // lags of 0 are left out, and the rest are all on the grid
+        #[cfg(feature = "autocorrelation")]
+        assert_bpm(autocorrelation(&offsets), 120_f64);
+
+        // taps that all land at once have no tempo
+        for (name, estimator) in ESTIMATORS {
+            let estimate = estimator(&[1000, 1000, 1000]);
+            assert!(
+                matches!(estimate, Err(BpmCalculationError::NoDuration)),
+                "{name} gave {estimate:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_order() {
+        for (name, estimator) in ESTIMATORS {
+            let estimate = estimator(&[0, 1000, 500]);
+            assert!(
+                matches!(estimate, Err(BpmCalculationError::Unordered)),
+                "{name} gave {estimate:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn huge_offsets() {
+        let offsets = [u64::MAX - 1000, u64::MAX - 500, u64::MAX];
+        for (name, estimator) in ESTIMATORS {
+            let bpm = estimator(&offsets).expect(name);
+            assert!(bpm.is_finite() && bpm > 0_f64, "{name} gave {bpm}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "autocorrelation")]
+    fn autocorrelation_without_a_beat() {
+        // the interval only reaches the shortest period through the kernel's tail
+        assert!(matches!(
+            autocorrelation(&[0, 175]),
+            Err(BpmCalculationError::InsufficientData)
+        ));
+    }
+
+    /// Estimates are either errors or positive and finite, for any input
+    #[test]
+    fn arbitrary_taps() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..2000 {
+            let len = next() % 12;
+            // mostly small steps, with some huge values, repeats and steps back
+            let mut offsets: Vec<u64> = (0..len).map(|_| next() % 3000).collect();
+            match next() % 4 {
+                0 => offsets.iter_mut().for_each(|x| *x = x.wrapping_mul(next())),
+                1 => offsets.sort(),
+                _ => {
+                    offsets.sort();
+                    offsets.dedup();
+                }
+            }
+            for (name, estimator) in ESTIMATORS {
+                if let Ok(bpm) = estimator(&offsets) {
+                    assert!(
+                        bpm.is_finite() && bpm > 0_f64,
+                        "{name} gave {bpm} for {offsets:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn long_gaps() {
+        // three taps an hour apart
+        let offsets = [0, 3_600_000, 7_200_000];
+        // the comb filter looks for beats and finds none, checked below
+        let beats = ESTIMATORS.iter().filter(|(name, _)| *name != "autocorr");
+        for (name, estimator) in beats {
+            let bpm = estimator(&offsets).expect(name);
+            assert!((bpm - 1_f64 / 60_f64).abs() < 1e-9, "{name} gave {bpm}");
+        }
+        // no interval is short enough to be a beat
+        #[cfg(feature = "autocorrelation")]
+        assert!(matches!(
+            autocorrelation(&offsets),
+            Err(BpmCalculationError::InsufficientData)
+        ));
+    }
+
+    #[test]
+    fn finite_guard() {
+        assert_eq!(finite(120_f64), Ok(120_f64));
+        for bpm in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(finite(bpm), Err(BpmCalculationError::NotFinite));
+        }
+    }
+
+    #[test]
+    fn plausible_guard() {
+        assert_eq!(plausible(400_f64, 400), Ok(400_f64));
+        assert_eq!(
+            plausible(742.31, 400),
+            Err(BpmCalculationError::Implausible(400))
+        );
+        assert_eq!(plausible(742.31, 0), Ok(742.31));
+    }
+
+    #[test]
+    fn pairwise_slopes_in_pair_order() {
+        let slopes = pairwise_slopes(&LATE);
+        let expected = [
+            1.0 / 600.0,
+            2.0 / 1000.0,
+            3.0 / 1500.0,
+            1.0 / 400.0,
+            2.0 / 900.0,
+            0.002,
+        ];
+        assert_eq!(slopes, expected);
+    }
+
+    #[test]
+    fn upper_median() {
+        assert_eq!(median(vec![3_f64, 1_f64, 2_f64]), 2_f64);
+        assert_eq!(median(vec![4_f64, 1_f64, 3_f64, 2_f64]), 3_f64);
+    }
+
+    #[test]
+    fn double_taps_merge_into_the_first() {
+        let offsets = [0, 500, 540, 1000, 1500];
+        assert_eq!(merge_double_taps(&offsets, 0.4), vec![0, 500, 1000, 1500]);
+        assert_eq!(merge_double_taps(&offsets, 0.05), offsets.to_vec());
+    }
 }
@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+
+use leptos::logging::error;
+use leptos::prelude::*;
+use leptos_use::{
+    use_raf_fn_with_options, use_user_media, UseRafFnCallbackArgs, UseRafFnOptions,
+    UseUserMediaReturn,
+};
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement};
+
+use crate::onset::OnsetDetector;
+use crate::watchdog;
+
+/// Frames are scaled down to this many pixels before being compared
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+/// How much brighter (out of 255) a pixel must get between frames to count as flashing
+const FLASH_DELTA: i32 = 32;
+
+/// Percent of pixels that got noticeably brighter since the previous frame
+fn flashed_percent(previous: &[u8], current: &[u8]) -> f64 {
+    let luma = |px: &[u8]| (px[0] as i32 * 299 + px[1] as i32 * 587 + px[2] as i32 * 114) / 1000;
+    let flashed = previous
+        .chunks_exact(4)
+        .zip(current.chunks_exact(4))
+        .filter(|(before, after)| luma(after) - luma(before) >= FLASH_DELTA)
+        .count();
+    100_f64 * flashed as f64 / (WIDTH * HEIGHT) as f64
+}
+
+/// Calls `on_beat` whenever the camera sees a flash, e.g. a drum machine's tempo LED
+/// `sensitivity` goes from 1 (the flash must fill the frame) to 9 (a few pixels is
+/// enough); 0 turns the camera off
+pub fn use_camera_beats(sensitivity: ReadSignal<u64>, on_beat: impl Fn() + 'static) {
+    let UseUserMediaReturn {
+        stream,
+        start,
+        stop,
+        ..
+    } = use_user_media();
+
+    let video = document()
+        .create_element("video")
+        .expect("video elements can be created")
+        .unchecked_into::<HtmlVideoElement>();
+    video.set_muted(true);
+    // keeps iOS from taking the video fullscreen
+    let _ = video.set_attribute("playsinline", "");
+    let canvas = document()
+        .create_element("canvas")
+        .expect("canvas elements can be created")
+        .unchecked_into::<HtmlCanvasElement>();
+    canvas.set_width(WIDTH);
+    canvas.set_height(HEIGHT);
+    let context = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .expect("2d canvas context is supported")
+        .unchecked_into::<CanvasRenderingContext2d>();
+
+    let previous_frame = RefCell::new(Vec::<u8>::new());
+    let detector = RefCell::new(OnsetDetector::default());
+    let raf = {
+        let video = video.clone();
+        use_raf_fn_with_options(
+            move |UseRafFnCallbackArgs { timestamp, .. }| {
+                // no frame is available until the stream starts playing
+                if video.video_width() == 0 {
+                    return;
+                }
+                let (w, h) = (WIDTH as f64, HEIGHT as f64);
+                let frame = context
+                    .draw_image_with_html_video_element_and_dw_and_dh(&video, 0.0, 0.0, w, h)
+                    .and_then(|_| context.get_image_data(0.0, 0.0, w, h));
+                let Ok(frame) = frame else {
+                    return;
+                };
+                let current = frame.data().0;
+                let previous = previous_frame.replace(current);
+                if previous.is_empty() {
+                    return;
+                }
+                let percent = flashed_percent(&previous, &previous_frame.borrow());
+                let threshold = 0.25 * (10 - sensitivity.get_untracked()) as f64;
+                if detector.borrow_mut().sample(percent, threshold, timestamp) {
+                    on_beat();
+                }
+            },
+            UseRafFnOptions::default().immediate(false),
+        )
+    };
+
+    Effect::new(move |_| {
+        if sensitivity.get() > 0 {
+            start();
+        } else {
+            stop();
+        }
+    });
+
+    let idle = watchdog::idle();
+    Effect::new(move |_| match stream.get() {
+        Some(Ok(stream)) => {
+            video.set_src_object(Some(&stream));
+            let _ = video.play();
+        }
+        Some(Err(e)) => error!("camera unavailable: {e:?}"),
+        None => video.set_src_object(None),
+    });
+
+    Effect::new(move |_| {
+        if matches!(stream.get(), Some(Ok(_))) && !idle.get() {
+            (raf.resume)();
+        } else {
+            (raf.pause)();
+        }
+    });
+}
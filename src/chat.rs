@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use leptos::prelude::*;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{MessageEvent, WebSocket};
+
+use crate::crowd::{crowd_tempo, Crowd, MIN_TAPS};
+use crate::rate::format_rate;
+
+const TWITCH_CHAT: &str = "wss://irc-ws.chat.twitch.tv:443";
+/// Chat message that counts as a tap
+const COMMAND: &str = "!tap";
+/// Viewers who haven't tapped for this long are left out, in milliseconds
+const STALE_MS: u64 = 10_000;
+/// Taps kept per viewer, older ones are forgotten
+const MAX_TAPS: usize = 16;
+
+/// The chat connection, with the handlers that have to live as long as it does
+struct Connection {
+    socket: WebSocket,
+    _on_open: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut()>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChatState {
+    Left,
+    Joining,
+    Joined,
+}
+
+/// Taps typed into a twitch channel's chat as `!tap`, each viewer's kept apart
+#[derive(Clone, Copy)]
+pub struct ChatTaps {
+    connection: StoredValue<Option<Connection>, LocalStorage>,
+    state: RwSignal<ChatState>,
+    /// Each viewer's recent taps, in milliseconds (performance.now)
+    viewers: RwSignal<HashMap<String, Vec<u64>>>,
+}
+
+impl ChatTaps {
+    fn join(&self, channel: &str) {
+        self.leave();
+        let channel = channel.trim().trim_start_matches('#').to_lowercase();
+        if channel.is_empty() {
+            return;
+        }
+        let Ok(socket) = WebSocket::new(TWITCH_CHAT) else {
+            return;
+        };
+
+        let on_open = {
+            let socket = socket.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                // justinfan nicks read chat anonymously, with any password
+                let nick = format!("justinfan{}", (js_sys::Math::random() * 1e5) as u32);
+                for line in [
+                    "PASS anonymous".into(),
+                    format!("NICK {nick}"),
+                    format!("JOIN #{channel}"),
+                ] {
+                    let _ = socket.send_with_str(&line);
+                }
+            })
+        };
+        let on_message = {
+            let (socket, chat) = (socket.clone(), *self);
+            Closure::<dyn FnMut(MessageEvent)>::new(move |evt: MessageEvent| {
+                let Some(data) = evt.data().as_string() else {
+                    return;
+                };
+                for line in data.lines() {
+                    if line.starts_with("PING") {
+                        let _ = socket.send_with_str(&line.replacen("PING", "PONG", 1));
+                    } else if line.contains(" JOIN #") {
+                        chat.state.set(ChatState::Joined);
+                    } else if let Some((viewer, text)) = chat_message(line) {
+                        if text.trim().eq_ignore_ascii_case(COMMAND) {
+                            chat.record(viewer, window().performance().map_or(0_f64, |p| p.now()));
+                        }
+                    }
+                }
+            })
+        };
+        let on_close = {
+            let state = self.state;
+            Closure::<dyn FnMut()>::new(move || state.set(ChatState::Left))
+        };
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        self.connection.set_value(Some(Connection {
+            socket,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        }));
+        self.state.set(ChatState::Joining);
+    }
+
+    fn leave(&self) {
+        if let Some(connection) = self.connection.write_value().take() {
+            connection.socket.set_onmessage(None);
+            connection.socket.set_onclose(None);
+            let _ = connection.socket.close();
+        }
+        self.state.set(ChatState::Left);
+        self.viewers.write().clear();
+    }
+
+    fn record(&self, viewer: &str, time_ms: f64) {
+        let now = time_ms as u64;
+        self.viewers.update(|viewers| {
+            viewers.retain(|_, taps| taps.last().is_some_and(|&last| now - last < STALE_MS));
+            let taps = viewers.entry(viewer.to_string()).or_default();
+            taps.push(now);
+            if taps.len() > MAX_TAPS {
+                taps.remove(0);
+            }
+        });
+    }
+}
+
+pub fn use_chat_taps() -> ChatTaps {
+    ChatTaps {
+        connection: StoredValue::new_local(None),
+        state: RwSignal::new(ChatState::Left),
+        viewers: RwSignal::new(HashMap::new()),
+    }
+}
+
+/// The sender and text of an irc chat message line
+fn chat_message(line: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let viewer = prefix.split('!').next()?;
+    Some((viewer, text))
+}
+
+#[component]
+pub fn ChatControls(chat: ChatTaps) -> impl IntoView {
+    let status = move || match chat.state.get() {
+        ChatState::Left => "enter joins twitch chat".to_string(),
+        ChatState::Joining => "joining".to_string(),
+        ChatState::Joined => format!("joined, viewers type {COMMAND}"),
+    };
+
+    view! {
+        <span class="text-label">"        chat:  "</span>
+        <input
+            class="bg-field outline-none w-[12ch] text-value"
+            placeholder="channel"
+            on:keydown=move |evt| {
+                if evt.key() == "Enter" {
+                    chat.join(&event_target_value(&evt));
+                }
+            }
+        />
+        <span class="text-comment">" # "{status}"\n"</span>
+    }
+}
+
+/// The crowd's tempo, while a chat is joined
+#[component]
+pub fn CrowdTempo(chat: ChatTaps, slow: ReadSignal<bool>) -> impl IntoView {
+    let crowd = Memo::new(move |_| {
+        chat.viewers
+            .with(|v| crowd_tempo(v.values().map(Vec::as_slice)))
+    });
+
+    view! {
+        <Show when=move || chat.state.get() == ChatState::Joined>
+            <span class="text-label">{format!("{:>12}: ", "crowd")}</span>
+            <span class="text-value">
+                {move || format_rate(crowd.get().map(|crowd| crowd.bpm), slow.get())}
+            </span>
+            <span class="text-comment">
+                {move || match crowd.get() {
+                    None => format!("# waiting for {MIN_TAPS} {COMMAND}s\n"),
+                    Some(Crowd { counted, latency_ms, ignored: 0, .. }) => {
+                        format!("# {counted} viewers ±{latency_ms:.0}ms\n")
+                    }
+                    Some(Crowd { counted, latency_ms, ignored, .. }) => {
+                        format!("# {counted} viewers ±{latency_ms:.0}ms, {ignored} ignored\n")
+                    }
+                }}
+            </span>
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chat_messages() {
+        let line = ":someone!someone@someone.tmi.twitch.tv PRIVMSG #channel :!tap";
+        assert_eq!(chat_message(line), Some(("someone", "!tap")));
+        assert_eq!(chat_message(":tmi.twitch.tv 001 justinfan1 :Welcome"), None);
+        assert_eq!(chat_message("PING :tmi.twitch.tv"), None);
+    }
+}
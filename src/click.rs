@@ -0,0 +1,517 @@
+use leptos::prelude::*;
+
+use crate::{export, Stepper};
+
+const SAMPLE_RATE: u32 = 22_050;
+/// How long each click rings, in seconds
+const CLICK_SECS: f64 = 0.03;
+/// Accented clicks are this much higher
+const ACCENT_PITCH: f64 = 1.5;
+/// Soft clicks are this much quieter than the other unaccented ones
+const SOFT_GAIN: f64 = 0.5;
+/// The bar pattern clicked unless another is typed in, one accented beat and three plain
+const PATTERN: &str = "Xxxx";
+/// Volumes are set in tenths
+const VOLUME_STEPS: u64 = 10;
+/// Ramps are set in steps of this many percent of the tapped tempo
+const RAMP_STEP_PERCENT: u64 = 5;
+/// Beats in a bar when the pattern is empty
+const BAR_BEATS: u64 = 4;
+
+/// How a beat of the bar is clicked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Accent {
+    Strong,
+    Weak,
+    Soft,
+    Rest,
+}
+
+/// The beats of a bar written as `X` for strong, `x` for weak, `o` for soft and `-` for a
+/// rest, skipping anything else
+fn parse_pattern(text: &str) -> Vec<Accent> {
+    text.chars()
+        .filter_map(|c| match c {
+            'X' => Some(Accent::Strong),
+            'x' => Some(Accent::Weak),
+            'o' => Some(Accent::Soft),
+            '-' | '.' => Some(Accent::Rest),
+            _ => None,
+        })
+        .collect()
+}
+
+/// What each click sounds like
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClickSound {
+    Beep,
+    Wood,
+    Low,
+}
+
+impl ClickSound {
+    pub const ALL: [ClickSound; 3] = [Self::Beep, Self::Wood, Self::Low];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Beep => "beep",
+            Self::Wood => "wood block",
+            Self::Low => "low beep",
+        }
+    }
+
+    fn pitch_hz(&self) -> f64 {
+        match self {
+            Self::Beep => 1_000.0,
+            Self::Wood => 2_500.0,
+            Self::Low => 440.0,
+        }
+    }
+
+    /// How fast the click dies away, higher is shorter
+    fn decay(&self) -> f64 {
+        match self {
+            Self::Wood => 300.0,
+            _ => 100.0,
+        }
+    }
+}
+
+/// A second stream of `clicks` evenly spread over every `beats` beats, so 3 over 2 plays
+/// three against the two of the tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Polyrhythm {
+    pub clicks: u64,
+    pub beats: u64,
+    pub sound: ClickSound,
+}
+
+impl Polyrhythm {
+    /// Seconds to each of its clicks, placed between the seconds to each beat in `beat_secs`
+    /// so the stream follows the tempo through a ramp
+    fn click_secs(&self, beat_secs: &[f64]) -> Vec<f64> {
+        (0..)
+            .map(|click| (click * self.beats) as f64 / self.clicks as f64)
+            .map_while(|beat| {
+                let before = beat.floor() as usize;
+                let (&from, &to) = (beat_secs.get(before)?, beat_secs.get(before + 1)?);
+                Some(from + (to - from) * beat.fract())
+            })
+            .collect()
+    }
+}
+
+/// How the clicks of a track are played
+#[derive(Debug, Clone)]
+pub struct ClickStyle {
+    /// Accent of each beat of the bar, every beat a weak one if empty
+    pub pattern: Vec<Accent>,
+    pub sound: ClickSound,
+    pub accent_sound: ClickSound,
+    /// Volume of strong beats and of the rest, from 0 to 1
+    pub accent_gain: f64,
+    pub gain: f64,
+    /// Played over the beats at `gain`, if set
+    pub poly: Option<Polyrhythm>,
+}
+
+impl Default for ClickStyle {
+    fn default() -> Self {
+        ClickStyle {
+            pattern: parse_pattern(PATTERN),
+            sound: ClickSound::Beep,
+            accent_sound: ClickSound::Beep,
+            accent_gain: 1.0,
+            gain: 0.5,
+            poly: None,
+        }
+    }
+}
+
+impl ClickStyle {
+    /// The sound, pitch and volume `beat` is clicked with, if it isn't a rest
+    fn click(&self, beat: u64) -> Option<(ClickSound, f64, f64)> {
+        let accent = match self.pattern.len() {
+            0 => Accent::Weak,
+            len => self.pattern[(beat % len as u64) as usize],
+        };
+        match accent {
+            Accent::Strong => Some((
+                self.accent_sound,
+                self.accent_sound.pitch_hz() * ACCENT_PITCH,
+                self.accent_gain,
+            )),
+            Accent::Weak => Some((self.sound, self.sound.pitch_hz(), self.gain)),
+            Accent::Soft => Some((self.sound, self.sound.pitch_hz(), self.gain * SOFT_GAIN)),
+            Accent::Rest => None,
+        }
+    }
+}
+
+/// A tempo that changes evenly from `from` to `to` bpm over `beats` beats and stays at `to`
+/// after, or stays at `from` throughout if `beats` is 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ramp {
+    pub from: f64,
+    pub to: f64,
+    pub beats: u64,
+}
+
+impl Ramp {
+    pub fn steady(bpm: f64) -> Self {
+        Ramp {
+            from: bpm,
+            to: bpm,
+            beats: 0,
+        }
+    }
+
+    /// Seconds from the first beat to each beat, endlessly
+    /// Beats after the ramp are placed from their own number rather than the one before, so
+    /// the tempo doesn't drift from rounding even over a long track
+    fn beat_secs(self) -> impl Iterator<Item = f64> {
+        let step = (self.to - self.from) / self.beats.max(1) as f64;
+        let ramp: Vec<f64> = (0..=self.beats)
+            .scan(0_f64, move |secs, beat| {
+                let at = *secs;
+                *secs += 60_f64 / (self.from + step * beat as f64);
+                Some(at)
+            })
+            .collect();
+        let ramped = ramp[ramp.len() - 1];
+        let period = 60_f64 / if self.beats == 0 { self.from } else { self.to };
+        let after = (1..).map(move |beat| ramped + beat as f64 * period);
+        ramp.into_iter().chain(after)
+    }
+}
+
+/// A mono 16-bit WAV of `seconds` of clicks following `tempo`, each bar clicked in `style`
+pub fn click_track_wav(tempo: Ramp, seconds: f64, style: &ClickStyle) -> Vec<u8> {
+    let rate = SAMPLE_RATE as f64;
+    let len = (seconds * rate) as usize;
+    let mut samples = vec![0_f64; len];
+    // one beat past the end, for the polyrhythm to place its last clicks against
+    let mut beat_secs = vec![];
+    for secs in tempo.beat_secs() {
+        beat_secs.push(secs);
+        if secs >= seconds {
+            break;
+        }
+    }
+    let mut click = |secs: f64, sound: ClickSound, pitch: f64, gain: f64| {
+        let start = ((secs * rate).round() as usize).min(len);
+        let end = (start + (CLICK_SECS * rate) as usize).min(len);
+        // the streams add up where they meet
+        for (i, sample) in samples[start..end].iter_mut().enumerate() {
+            let t = i as f64 / rate;
+            *sample +=
+                gain * (-sound.decay() * t).exp() * (std::f64::consts::TAU * pitch * t).sin();
+        }
+    };
+    for (beat, &secs) in beat_secs.iter().enumerate() {
+        if let Some((sound, pitch, gain)) = style.click(beat as u64) {
+            click(secs, sound, pitch, gain);
+        }
+    }
+    if let Some(poly) = style.poly {
+        for secs in poly.click_secs(&beat_secs) {
+            click(secs, poly.sound, poly.sound.pitch_hz(), style.gain);
+        }
+    }
+
+    let data: Vec<u8> = samples
+        .iter()
+        .flat_map(|s| ((s.clamp(-1_f64, 1_f64) * 0.8 * i16::MAX as f64) as i16).to_le_bytes())
+        .collect();
+    let mut wav = wav_header(data.len() as u32);
+    wav.extend(data);
+    wav
+}
+
+/// The 44 byte header of a mono 16-bit PCM WAV with `data_len` bytes of samples
+fn wav_header(data_len: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(44);
+    header.extend(b"RIFF");
+    header.extend((36 + data_len).to_le_bytes());
+    header.extend(b"WAVEfmt ");
+    header.extend(16_u32.to_le_bytes()); // format chunk size
+    header.extend(1_u16.to_le_bytes()); // pcm
+    header.extend(1_u16.to_le_bytes()); // mono
+    header.extend(SAMPLE_RATE.to_le_bytes());
+    header.extend((SAMPLE_RATE * 2).to_le_bytes()); // bytes per second
+    header.extend(2_u16.to_le_bytes()); // bytes per sample
+    header.extend(16_u16.to_le_bytes()); // bits per sample
+    header.extend(b"data");
+    header.extend(data_len.to_le_bytes());
+    header
+}
+
+/// Settings for the click track, and a button that renders and downloads it
+#[component]
+pub fn ClickExport(bpm: Signal<Option<f64>>) -> impl IntoView {
+    let minutes = RwSignal::new(1);
+    let pattern = RwSignal::new(PATTERN.to_string());
+    // 1-based indices into `ClickSound::ALL`
+    let sound = RwSignal::new(1);
+    let accent_sound = RwSignal::new(1);
+    // the tempo the track ends on, in steps of the tapped one, and the bars it takes to get there
+    let ramp_steps = RwSignal::new(100 / RAMP_STEP_PERCENT);
+    let ramp_bars = RwSignal::new(8);
+    let volume = RwSignal::new(VOLUME_STEPS / 2);
+    let accent_volume = RwSignal::new(VOLUME_STEPS);
+    // clicks of the second stream, 0 for none, over this many beats
+    let poly_clicks = RwSignal::new(0);
+    let poly_beats = RwSignal::new(2);
+    let poly_sound = RwSignal::new(2);
+    let sound_of = |index: RwSignal<u64>| ClickSound::ALL[index.get() as usize - 1];
+    let ramp_of =
+        move |bpm: f64, bar_beats: u64| match ramp_steps.get_untracked() * RAMP_STEP_PERCENT {
+            100 => Ramp::steady(bpm),
+            percent => Ramp {
+                from: bpm,
+                to: bpm * percent as f64 / 100_f64,
+                beats: ramp_bars.get_untracked() * bar_beats,
+            },
+        };
+    let export = move |_| {
+        let Some(bpm) = bpm.get_untracked() else {
+            return;
+        };
+        let seconds = minutes.get_untracked() as f64 * 60_f64;
+        let style = untrack(|| ClickStyle {
+            pattern: parse_pattern(&pattern.get()),
+            sound: sound_of(sound),
+            accent_sound: sound_of(accent_sound),
+            accent_gain: accent_volume.get() as f64 / VOLUME_STEPS as f64,
+            gain: volume.get() as f64 / VOLUME_STEPS as f64,
+            poly: (poly_clicks.get() != 0).then(|| Polyrhythm {
+                clicks: poly_clicks.get(),
+                beats: poly_beats.get(),
+                sound: sound_of(poly_sound),
+            }),
+        });
+        let bar_beats = match style.pattern.len() {
+            0 => BAR_BEATS,
+            len => len as u64,
+        };
+        let tempo = ramp_of(bpm, bar_beats);
+        let name = if tempo.from == tempo.to {
+            format!("click-{bpm:.2}.wav")
+        } else {
+            format!("click-{bpm:.2}-{:.2}.wav", tempo.to)
+        };
+        let wav = click_track_wav(tempo, seconds, &style);
+        export::download_bytes(&name, "audio/wav", &wav);
+    };
+    let ramp_to = move || match (ramp_steps.get() * RAMP_STEP_PERCENT, bpm.get()) {
+        (100, _) => "steady, no ramp".to_string(),
+        (percent, None) => format!("ramps to {percent}% of the tempo"),
+        (percent, Some(bpm)) => format!("ramps to {:.2} bpm", bpm * percent as f64 / 100_f64),
+    };
+    let poly = move || match (poly_clicks.get(), poly_beats.get()) {
+        (0, _) => "clicks against the beat, 0 is off".to_string(),
+        (clicks, beats) => format!("{clicks}:{beats}, {clicks} clicks over {beats} beats"),
+    };
+    let volume_of = |volume: RwSignal<u64>| {
+        Signal::derive(move || format!("{}% volume", volume.get() * 100 / VOLUME_STEPS))
+    };
+
+    view! {
+        <span class="text-label">"       click:  "</span>
+        <button class="hover:text-value" on:mousedown=export>
+            "export"
+        </button>
+        <span class="text-comment">
+            {move || match bpm.get() {
+                Some(bpm) => format!(" # wav at {bpm:.2} bpm\n"),
+                None => " # tap a tempo first\n".to_string(),
+            }}
+        </span>
+        <Stepper
+            label="click-min"
+            value=minutes.read_only()
+            set_value=minutes.write_only()
+            min=1
+            max=9
+            description="minutes of clicks"
+        />
+        <Stepper
+            label="click-ramp"
+            value=ramp_steps.read_only()
+            set_value=ramp_steps.write_only()
+            min=50 / RAMP_STEP_PERCENT
+            max=200 / RAMP_STEP_PERCENT
+            description=Signal::derive(ramp_to)
+        />
+        <Stepper
+            label="ramp-bars"
+            value=ramp_bars.read_only()
+            set_value=ramp_bars.write_only()
+            min=1
+            max=64
+            description="bars the ramp takes"
+        />
+        <span class="text-label">"   click-bar:  "</span>
+        <input
+            class="bg-field outline-none w-[12ch] text-value"
+            prop:value=move || pattern.get()
+            on:change=move |evt| pattern.set(event_target_value(&evt))
+        />
+        <span class="text-comment">" # X strong, x weak, o soft, - rest\n"</span>
+        <Stepper
+            label="click-sound"
+            value=sound.read_only()
+            set_value=sound.write_only()
+            min=1
+            max=ClickSound::ALL.len() as u64
+            description=Signal::derive(move || sound_of(sound).name().to_string())
+        />
+        <Stepper
+            label="click-vol"
+            value=volume.read_only()
+            set_value=volume.write_only()
+            min=1
+            max=VOLUME_STEPS
+            description=volume_of(volume)
+        />
+        <Stepper
+            label="accent-sound"
+            value=accent_sound.read_only()
+            set_value=accent_sound.write_only()
+            min=1
+            max=ClickSound::ALL.len() as u64
+            description=Signal::derive(move || sound_of(accent_sound).name().to_string())
+        />
+        <Stepper
+            label="accent-vol"
+            value=accent_volume.read_only()
+            set_value=accent_volume.write_only()
+            min=1
+            max=VOLUME_STEPS
+            description=volume_of(accent_volume)
+        />
+        <Stepper
+            label="poly-clicks"
+            value=poly_clicks.read_only()
+            set_value=poly_clicks.write_only()
+            min=0
+            max=9
+            description=Signal::derive(poly)
+        />
+        <Stepper
+            label="poly-beats"
+            value=poly_beats.read_only()
+            set_value=poly_beats.write_only()
+            min=1
+            max=9
+            description="beats the clicks are spread over"
+        />
+        <Stepper
+            label="poly-sound"
+            value=poly_sound.read_only()
+            set_value=poly_sound.write_only()
+            min=1
+            max=ClickSound::ALL.len() as u64
+            description=Signal::derive(move || sound_of(poly_sound).name().to_string())
+        />
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_sizes() {
+        let wav = click_track_wav(Ramp::steady(120_f64), 1_f64, &ClickStyle::default());
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 2 * SAMPLE_RATE as usize);
+        assert_eq!(
+            u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize,
+            wav.len() - 8
+        );
+        assert_eq!(
+            u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize,
+            wav.len() - 44
+        );
+    }
+
+    #[test]
+    fn clicks_fall_on_the_beat() {
+        let style = ClickStyle {
+            pattern: vec![],
+            ..ClickStyle::default()
+        };
+        let wav = click_track_wav(Ramp::steady(120_f64), 2_f64, &style);
+        let samples: Vec<i16> = wav[44..]
+            .chunks(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        // at 120 bpm a click starts every half second, silence in between
+        let half = SAMPLE_RATE as usize / 2;
+        for beat in 0..4 {
+            assert!(samples[beat * half + 1] != 0);
+            assert_eq!(samples[beat * half + half / 2], 0);
+        }
+    }
+
+    #[test]
+    fn clicks_the_bar_pattern() {
+        use Accent::*;
+        assert_eq!(parse_pattern("X x o -"), vec![Strong, Weak, Soft, Rest]);
+        let style = ClickStyle {
+            pattern: parse_pattern("Xo-"),
+            accent_sound: ClickSound::Wood,
+            ..ClickStyle::default()
+        };
+        assert_eq!(style.click(3), Some((ClickSound::Wood, 3_750_f64, 1_f64)));
+        assert_eq!(style.click(4), Some((ClickSound::Beep, 1_000_f64, 0.25)));
+        assert_eq!(style.click(5), None);
+    }
+
+    #[test]
+    fn ramps_the_tempo() {
+        // 60 up to 240 bpm over two beats, with the second beat at 150, then 240 on
+        let ramp = Ramp {
+            from: 60_f64,
+            to: 240_f64,
+            beats: 2,
+        };
+        let secs: Vec<f64> = ramp.beat_secs().take(5).collect();
+        for (secs, expected) in secs.into_iter().zip([0_f64, 1_f64, 1.4, 1.65, 1.9]) {
+            assert!(
+                (secs - expected).abs() < 1e-9,
+                "expected {expected}, got {secs}"
+            );
+        }
+        let steady: Vec<f64> = Ramp::steady(120_f64).beat_secs().take(3).collect();
+        assert_eq!(steady, vec![0_f64, 0.5, 1_f64]);
+    }
+
+    #[test]
+    fn plays_three_against_two() {
+        let poly = Polyrhythm {
+            clicks: 3,
+            beats: 2,
+            sound: ClickSound::Wood,
+        };
+        // a beat a second, then a ramp into half seconds
+        let secs = poly.click_secs(&[0_f64, 1_f64, 2_f64, 2.5, 3_f64]);
+        let third = 1_f64 / 3_f64;
+        let expected = [
+            0_f64,
+            2_f64 * third,
+            4_f64 * third,
+            2_f64,
+            2_f64 + third,
+            2.5 + third / 2_f64,
+        ];
+        assert_eq!(secs.len(), expected.len());
+        for (secs, expected) in secs.into_iter().zip(expected) {
+            assert!(
+                (secs - expected).abs() < 1e-9,
+                "expected {expected}, got {secs}"
+            );
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::bpm::{median, thiel_sen, BpmCalculationError};
+
+/// Taps a person needs before their tempo counts
+pub const MIN_TAPS: usize = 3;
+/// People always count if they are this close to the crowd's median
+const AGREEMENT_BPM: f64 = 2.0;
+
+/// One tempo for a crowd tapping along together
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crowd {
+    pub bpm: f64,
+    /// Median distance (ms) between a person's latency and the crowd's
+    pub latency_ms: f64,
+    /// People whose taps went into `bpm`
+    pub counted: usize,
+    /// People too far from everyone else to count
+    pub ignored: usize,
+}
+
+/// Leaves out people whose own tempo is more than a few median absolute deviations from
+/// the median, then fuses everyone else's taps
+pub fn crowd_tempo<'a>(people: impl IntoIterator<Item = &'a [u64]>) -> Option<Crowd> {
+    let estimates: Vec<(&[u64], f64)> = people
+        .into_iter()
+        .filter(|taps| taps.len() >= MIN_TAPS)
+        .filter_map(|taps| Some((taps, thiel_sen(taps).ok()?)))
+        .collect();
+    if estimates.is_empty() {
+        return None;
+    }
+    let middle = median(estimates.iter().map(|&(_, bpm)| bpm).collect());
+    let spread = median(
+        estimates
+            .iter()
+            .map(|(_, bpm)| (bpm - middle).abs())
+            .collect(),
+    );
+    // 1.4826 scales the MAD to a standard deviation for normally distributed tempos
+    let limit = (3_f64 * 1.4826 * spread).max(AGREEMENT_BPM);
+    let counted: Vec<&[u64]> = estimates
+        .iter()
+        .filter(|(_, bpm)| (bpm - middle).abs() <= limit)
+        .map(|&(taps, _)| taps)
+        .collect();
+    let fused = fuse(&counted).ok()?;
+    Some(Crowd {
+        bpm: fused.bpm,
+        latency_ms: median(fused.offsets.iter().map(|o| o.abs()).collect()),
+        counted: counted.len(),
+        ignored: estimates.len() - counted.len(),
+    })
+}
+
+/// One beat grid for several people tapping the same beat
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fused {
+    pub bpm: f64,
+    /// How far (ms) behind the crowd each person taps, negative is ahead
+    pub offsets: Vec<f64>,
+}
+
+/// Fits one tempo to everyone's taps at once with an intercept per person, so a constant
+/// latency, or starting on a different beat, moves that person's line without bending the
+/// slope: it is the covariance over the variance of everyone's taps about their own means
+pub fn fuse(people: &[&[u64]]) -> Result<Fused, BpmCalculationError> {
+    let people: Vec<&[u64]> = people.iter().copied().filter(|t| t.len() >= 2).collect();
+    if people.is_empty() {
+        return Err(BpmCalculationError::InsufficientData);
+    }
+    if !people.iter().all(|taps| taps.is_sorted()) {
+        return Err(BpmCalculationError::Unordered);
+    }
+
+    let (mut covariance, mut variance) = (0_f64, 0_f64);
+    // each person's mean offset and beat, which their line goes through
+    let mut centers = Vec::with_capacity(people.len());
+    for taps in &people {
+        // measured from the first tap, so the offsets convert to f64 exactly
+        let xs = || taps.iter().map(|&x| (x - taps[0]) as f64);
+        let n = taps.len() as f64;
+        let (mean_x, mean_y) = (xs().sum::<f64>() / n, (n - 1_f64) / 2_f64);
+        for (y, x) in xs().enumerate() {
+            let dx = x - mean_x;
+            covariance += dx * (y as f64 - mean_y);
+            variance += dx * dx;
+        }
+        centers.push((taps[0] as f64 + mean_x, mean_y));
+    }
+    if variance <= 0_f64 {
+        return Err(BpmCalculationError::NoDuration);
+    }
+    let period = variance / covariance; // milliseconds per beat
+
+    // where each person's grid puts a beat, folded to within half a beat of the first person's
+    let phases: Vec<f64> = centers
+        .iter()
+        .map(|&(x, y)| {
+            let beat = x - y * period - (centers[0].0 - centers[0].1 * period);
+            beat - (beat / period).round() * period
+        })
+        .collect();
+    let middle = median(phases.clone());
+    Ok(Fused {
+        bpm: 60_000_f64 / period,
+        offsets: phases.iter().map(|phase| phase - middle).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuse_removes_each_latency() {
+        // the second person is 70ms late and joins a beat in, the third 30ms early
+        let fused = fuse(&[
+            &[0, 500, 1000, 1500],
+            &[570, 1070, 1570],
+            &[970, 1470, 1970, 2470],
+        ])
+        .unwrap();
+        assert!((fused.bpm - 120_f64).abs() < 1e-9);
+        let expected = [0_f64, 70_f64, -30_f64];
+        for (offset, expected) in fused.offsets.iter().zip(expected) {
+            assert!((offset - expected).abs() < 1e-9, "{:?}", fused.offsets);
+        }
+    }
+
+    #[test]
+    fn fuse_rejects_unusable_taps() {
+        assert_eq!(fuse(&[&[1000]]), Err(BpmCalculationError::InsufficientData));
+        assert_eq!(fuse(&[&[500, 0]]), Err(BpmCalculationError::Unordered));
+        assert_eq!(fuse(&[&[0, 0]]), Err(BpmCalculationError::NoDuration));
+    }
+
+    #[test]
+    fn crowd_ignores_the_odd_one_out() {
+        let people: [&[u64]; 4] = [
+            &[0, 500, 1000, 1500],
+            &[120, 620, 1120, 1620],
+            &[40, 540, 1040],
+            &[0, 250, 500, 750],
+        ];
+        let crowd = crowd_tempo(people).unwrap();
+        assert_eq!((crowd.counted, crowd.ignored), (3, 1));
+        assert!((crowd.bpm - 120_f64).abs() < 1e-9);
+        // offsets of -40, 80 and 0ms from the median phase
+        assert!((crowd.latency_ms - 40_f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn crowd_needs_enough_taps() {
+        assert_eq!(crowd_tempo([&[0_u64, 500][..]]), None);
+    }
+}
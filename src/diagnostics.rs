@@ -0,0 +1,151 @@
+use js_sys::{Promise, Reflect};
+use leptos::ev::{keydown, mousedown};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::{use_document, use_event_listener};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::AudioContext;
+
+use crate::fps::FrameRate;
+
+/// Nested timeouts run back to back, browsers start clamping them after the fifth
+const TIMER_DEPTH: usize = 10;
+/// Timestamps read for the clock's resolution
+const CLOCK_SAMPLES: usize = 1000;
+/// Taps kept for the event clock's resolution
+const EVENT_SAMPLES: usize = 16;
+
+/// The finest step, in ms, seen between whole values of `times_ms`, down to a microsecond
+fn resolution(times_ms: &[f64]) -> Option<f64> {
+    times_ms
+        .iter()
+        .map(|&time| {
+            let mut micros = (time * 1_000_f64).round() as u64;
+            let mut step = 1_u64;
+            while step < 1_000 && micros > 0 && micros.is_multiple_of(10) {
+                micros /= 10;
+                step *= 10;
+            }
+            step
+        })
+        .min()
+        .map(|step| step as f64 / 1_000_f64)
+}
+
+/// How far taps timed at `resolution` ms can be trusted
+fn trust(resolution: f64) -> &'static str {
+    match resolution {
+        r if r <= 0.1 => "precise",
+        r if r <= 1.0 => "fine",
+        _ => "coarse",
+    }
+}
+
+fn now() -> f64 {
+    window().performance().map_or(0_f64, |p| p.now())
+}
+
+/// Resolves after a `setTimeout(0)`
+async fn zero_timeout() {
+    let wait = Promise::new(&mut |resolve, _| {
+        let _ = window().set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 0);
+    });
+    let _ = JsFuture::from(wait).await;
+}
+
+/// Mean delay of the nested timeouts past the point browsers clamp them
+async fn timer_clamp() -> f64 {
+    let mut delays = Vec::with_capacity(TIMER_DEPTH);
+    for _ in 0..TIMER_DEPTH {
+        let start = now();
+        zero_timeout().await;
+        delays.push(now() - start);
+    }
+    let clamped = &delays[TIMER_DEPTH / 2..];
+    clamped.iter().sum::<f64>() / clamped.len() as f64
+}
+
+/// Milliseconds between the audio graph and the speakers, where the browser reports it
+fn audio_latency() -> Option<f64> {
+    let context = AudioContext::new().ok()?;
+    let latency = ["outputLatency", "baseLatency"]
+        .into_iter()
+        .filter_map(|key| {
+            Reflect::get(&context, &JsValue::from_str(key))
+                .ok()?
+                .as_f64()
+        })
+        .find(|&latency| latency > 0_f64);
+    let _ = context.close();
+    latency.map(|seconds| seconds * 1_000_f64)
+}
+
+/// Checks the timers, clocks and outputs measurements here rely on
+#[component]
+pub fn SelfTest() -> impl IntoView {
+    let clamp = RwSignal::new(None::<f64>);
+    spawn_local(async move { clamp.set(Some(timer_clamp().await)) });
+    let clock: Vec<f64> = (0..CLOCK_SAMPLES).map(|_| now()).collect();
+    let clock = resolution(&clock);
+    let audio = audio_latency();
+
+    let events = RwSignal::new(Vec::<f64>::new());
+    let record = move |time: f64| {
+        events.update(|events| {
+            if events.len() == EVENT_SAMPLES {
+                events.remove(0);
+            }
+            events.push(time);
+        })
+    };
+    let _cleanup = use_event_listener(use_document(), mousedown, move |evt| {
+        record(evt.time_stamp())
+    });
+    let _cleanup = use_event_listener(use_document(), keydown, move |evt| record(evt.time_stamp()));
+    let event_clock = Memo::new(move |_| resolution(&events.read()));
+
+    let ms = |value: Option<f64>| match value {
+        Some(value) => format!("{value:6.3} "),
+        None => "  ---  ".into(),
+    };
+    let verdict = move || match event_clock.get() {
+        Some(step) => format!("# {}, taps timed to {step}ms\n", trust(step)),
+        None => "# tap to time the input events\n".into(),
+    };
+
+    view! {
+        "\n"
+        <span class="text-label">{format!("{:>12}: ", "timer-clamp")}</span>
+        <span class="text-value">{move || ms(clamp.get())}</span>
+        <span class="text-comment">"# ms per nested timeout(0)\n"</span>
+        <span class="text-label">{format!("{:>12}: ", "clock")}</span>
+        <span class="text-value">{ms(clock)}</span>
+        <span class="text-comment">"# ms steps of performance.now\n"</span>
+        <span class="text-label">{format!("{:>12}: ", "event-clock")}</span>
+        <span class="text-value">{move || ms(event_clock.get())}</span>
+        <span class="text-comment">"# ms steps of tap timestamps\n"</span>
+        <span class="text-label">{format!("{:>12}: ", "audio-out")}</span>
+        <span class="text-value">{ms(audio)}</span>
+        <span class="text-comment">"# ms until a click is heard\n"</span>
+        <span class="text-label">{format!("{:>12}: ", "trust")}</span>
+        <span class="text-value">{move || ms(event_clock.get().or(clock))}</span>
+        <span class="text-comment">{verdict}</span>
+        <FrameRate />
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_clock_step() {
+        assert_eq!(resolution(&[12.0, 1500.0]), Some(1.0));
+        assert_eq!(resolution(&[12.1, 15.0]), Some(0.1));
+        assert_eq!(resolution(&[12.345, 15.3]), Some(0.001));
+        assert_eq!(resolution(&[]), None);
+        assert_eq!(trust(0.005), "precise");
+        assert_eq!(trust(2.0), "coarse");
+    }
+}
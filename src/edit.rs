@@ -0,0 +1,101 @@
+use leptos::prelude::*;
+
+use crate::history::Command;
+use crate::{bpm, rate, TapData};
+
+/// Milliseconds one click of the arrows moves a tap by
+const NUDGE_MS: i64 = 10;
+
+/// The taps of a finished session, each of which can be nudged, deleted or split from the
+/// ones before it, and the session before it to join back on
+#[component]
+pub fn TapEditor(
+    tap_data: ReadSignal<TapData>,
+    set_tap_data: WriteSignal<TapData>,
+    slow: ReadSignal<bool>,
+) -> impl IntoView {
+    let run = move |command: Command| set_tap_data.write().run(command);
+    let rows = move || {
+        tap_data
+            .read()
+            .offsets()
+            .enumerate()
+            .map(|(index, offset)| {
+                view! {
+                    <span class="text-label">{format!("{:>12}:  ", index + 1)}</span>
+                    <button
+                        class="hover:text-value"
+                        on:mousedown=move |_| run(Command::Nudge(index, -NUDGE_MS))
+                    >
+                        "←"
+                    </button>
+                    <span class="text-value">{format!(" {offset:6} ")}</span>
+                    <button
+                        class="hover:text-value"
+                        on:mousedown=move |_| run(Command::Nudge(index, NUDGE_MS))
+                    >
+                        "→"
+                    </button>
+                    <span class="text-comment">" # ms, "</span>
+                    <button
+                        class="text-comment hover:text-value"
+                        on:mousedown=move |_| run(Command::Remove(index))
+                    >
+                        "delete"
+                    </button>
+                    <Show when=move || index != 0>
+                        " "
+                        <button
+                            class="text-comment hover:text-value"
+                            on:mousedown=move |_| run(Command::Split(index))
+                        >
+                            "split"
+                        </button>
+                    </Show>
+                    "\n"
+                }
+            })
+            .collect_view()
+    };
+
+    // a reset that came too soon splits a take in two, which joining puts back together
+    let previous = Memo::new(move |_| {
+        tap_data.with(|taps| {
+            let (previous, gap) = taps.previous()?;
+            Some((previous.len(), bpm::thiel_sen(previous).ok(), gap))
+        })
+    });
+
+    // a running session takes new taps from its last offset, which an edit could move past
+    view! {
+        "\n"
+        {move || {
+            previous
+                .get()
+                .map(|(taps, bpm, gap)| {
+                    let estimate = format!("# thiel-sen of its {taps} taps\n");
+                    view! {
+                        <span class="text-label">{format!("{:>12}: ", "previous")}</span>
+                        <span class="text-value">{rate::format_rate(bpm, slow.get())}</span>
+                        <span class="text-comment">{estimate}</span>
+                        <span class="text-label">{format!("{:>12}:  ", "join")}</span>
+                        <button class="hover:text-value" on:mousedown=move |_| run(Command::Join)>
+                            "yes"
+                        </button>
+                        <span class="text-comment">{format!(" # after a {gap}ms gap\n")}</span>
+                    }
+                })
+        }}
+        <Show
+            when=move || tap_data.read().is_reset()
+            fallback=|| {
+                view! {
+                    <span class="text-label">{format!("{:>12}: ", "taps")}</span>
+                    <span class="text-comment">"# taps can be edited once the session ends\n"</span>
+                }
+            }
+        >
+            {rows}
+        </Show>
+    }
+}
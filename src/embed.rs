@@ -0,0 +1,72 @@
+use js_sys::{Float64Array, Function};
+use leptos::prelude::*;
+use wasm_bindgen::prelude::*;
+
+use crate::rate::format_rate;
+
+/// A metric added by the host page, shown below the built in ones
+struct CustomMetric {
+    name: String,
+    estimate: Function,
+}
+
+type Registry = RwSignal<Vec<CustomMetric>, LocalStorage>;
+
+thread_local! {
+    static CUSTOM_METRICS: Registry = RwSignal::new_local(Vec::new());
+}
+
+/// Adds a row to the table computed by `estimate`, which is passed the tap offsets in ms as a
+/// `Float64Array` and returns a tempo in bpm; registering a name again replaces it
+///
+/// ```js
+/// wasmBindings.registerMetric("mean", (offsets) => 60000 * (offsets.length - 1) / offsets.at(-1));
+/// ```
+#[wasm_bindgen(js_name = registerMetric)]
+pub fn register_metric(name: String, estimate: Function) {
+    CUSTOM_METRICS.with(|metrics| {
+        metrics.update(|metrics| {
+            metrics.retain(|metric| metric.name != name);
+            metrics.push(CustomMetric { name, estimate });
+        })
+    });
+}
+
+impl CustomMetric {
+    /// Runs the host's estimator, treating anything but a finite number as no estimate
+    fn estimate(&self, offsets: &[u64]) -> Option<f64> {
+        let offsets: Vec<f64> = offsets.iter().map(|&offset| offset as f64).collect();
+        let offsets = Float64Array::from(offsets.as_slice());
+        self.estimate
+            .call1(&JsValue::NULL, &offsets)
+            .ok()?
+            .as_f64()
+            .filter(|bpm| bpm.is_finite())
+    }
+}
+
+#[component]
+pub fn CustomMetrics(offsets: Memo<Vec<u64>>, slow: ReadSignal<bool>) -> impl IntoView {
+    let metrics = CUSTOM_METRICS.with(|metrics| *metrics);
+    move || {
+        let names: Vec<String> = metrics.with(|m| m.iter().map(|m| m.name.clone()).collect());
+        names
+            .into_iter()
+            .map(|name| {
+                let label = format!("{:>12}: ", name.chars().take(12).collect::<String>());
+                // js functions can't move into the view, so each row looks its own up
+                let estimate = move || {
+                    metrics.with(|metrics| {
+                        let metric = metrics.iter().find(|metric| metric.name == name)?;
+                        metric.estimate(&offsets.read())
+                    })
+                };
+                view! {
+                    <span class="text-label">{label}</span>
+                    <span class="text-value">{move || format_rate(estimate(), slow.get())}</span>
+                    <span class="text-comment">"# registered by the page\n"</span>
+                }
+            })
+            .collect_view()
+    }
+}
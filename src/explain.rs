@@ -0,0 +1,124 @@
+use serde::Serialize;
+
+use crate::bpm::{self, median, BpmCalculationError};
+
+/// Estimates closer together than this are considered to agree
+const AGREEMENT_BPM: f64 = 0.05;
+/// Taps further off the beat grid than this are always worth pointing out
+const NOTABLE_MS: f64 = 15.0;
+
+/// One line of an explanation, attributed to the metric it describes
+#[derive(Serialize)]
+pub struct Note {
+    pub label: &'static str,
+    pub text: String,
+}
+
+fn note(label: &'static str, text: String) -> Note {
+    Note { label, text }
+}
+
+/// Generates a short breakdown of why the estimators disagree on `offsets`
+/// Residuals are measured against the thiel-sen fit, since it is the estimator
+/// least affected by the mistakes being explained
+pub fn explain(offsets: &[u64]) -> Result<Vec<Note>, BpmCalculationError> {
+    let direct = bpm::direct_count(offsets)?;
+    let lin_reg = bpm::simple_regression(offsets)?;
+    let fit = bpm::thiel_sen_fit(offsets)?;
+    let thiel_sen = fit.bpm();
+
+    if !thiel_sen.is_finite() || thiel_sen <= 0_f64 {
+        return Ok(vec![note(
+            "explain",
+            "taps are too close to fit a grid".into(),
+        )]);
+    }
+
+    // milliseconds between each tap and its beat on the grid, positive is late
+    let errors: Vec<f64> = offsets
+        .iter()
+        .enumerate()
+        .map(|(y, &x)| x as f64 - fit.offset_of(y as f64))
+        .collect();
+
+    let mut notes = Vec::new();
+
+    if (direct - thiel_sen).abs() >= AGREEMENT_BPM {
+        // direct only sees the endpoints, so blame whichever is further off
+        let (first, last) = (errors[0], errors[errors.len() - 1]);
+        let (which, error) = if first.abs() > last.abs() {
+            ("first", first)
+        } else {
+            ("last", last)
+        };
+        let text = format!(
+            "{}, {which} tap {}",
+            high_low(direct, thiel_sen),
+            timing(error)
+        );
+        notes.push(note("direct", text));
+    }
+
+    if (lin_reg - thiel_sen).abs() >= AGREEMENT_BPM {
+        // every tap pulls on the least squares line, the worst one hardest
+        let (tap, error) = errors
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .expect("offsets has at least two taps");
+        let text = format!(
+            "{}, pulled by tap {} ({})",
+            high_low(lin_reg, thiel_sen),
+            tap + 1,
+            timing(error)
+        );
+        notes.push(note("lin-reg", text));
+    }
+
+    // slopes outside the interquartile fences barely move the median
+    let mut slopes = bpm::pairwise_slopes(offsets);
+    slopes.sort_unstable_by(f64::total_cmp);
+    let (q1, q3) = (slopes[slopes.len() / 4], slopes[slopes.len() * 3 / 4]);
+    let fence = 1.5 * (q3 - q1);
+    let ignored = slopes
+        .iter()
+        .filter(|&&s| s < q1 - fence || s > q3 + fence)
+        .count();
+    if ignored > 0 {
+        let text = format!("ignored {ignored}/{} outlier pairs", slopes.len());
+        notes.push(note("thiel-sen", text));
+    }
+
+    // taps far outside the typical timing error are probably mistaps
+    let mad = median(errors.iter().map(|e| e.abs()).collect());
+    let threshold = NOTABLE_MS.max(3_f64 * 1.4826 * mad);
+    let mistaps: Vec<_> = errors
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.abs() > threshold)
+        .map(|(tap, _)| (tap + 1).to_string())
+        .collect();
+    if !mistaps.is_empty() {
+        let text = format!("tap {} (likely mistaps)", mistaps.join(", "));
+        notes.push(note("outliers", text));
+    }
+
+    if notes.is_empty() {
+        notes.push(note("explain", format!("all agree within {AGREEMENT_BPM}")));
+    }
+    Ok(notes)
+}
+
+fn high_low(estimate: f64, reference: f64) -> &'static str {
+    if estimate > reference {
+        "high"
+    } else {
+        "low"
+    }
+}
+
+fn timing(error_ms: f64) -> String {
+    let direction = if error_ms > 0_f64 { "late" } else { "early" };
+    format!("{:.0}ms {direction}", error_ms.abs())
+}
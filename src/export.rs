@@ -0,0 +1,56 @@
+use leptos::prelude::set_timeout;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+use web_time::Duration;
+
+/// How long a download's url is kept, some browsers only start reading it after the click
+const REVOKE_AFTER: Duration = Duration::from_secs(10);
+
+/// Has the browser save the text `contents` as a file called `name`
+pub fn download_text(name: &str, mime: &str, contents: &str) {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    download(
+        name,
+        Blob::new_with_str_sequence_and_options(&parts, &options(mime)),
+    );
+}
+
+/// Has the browser save `bytes` as a file called `name`
+pub fn download_bytes(name: &str, mime: &str, bytes: &[u8]) {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    download(
+        name,
+        Blob::new_with_u8_array_sequence_and_options(&parts, &options(mime)),
+    );
+}
+
+fn options(mime: &str) -> BlobPropertyBag {
+    let options = BlobPropertyBag::new();
+    options.set_type(mime);
+    options
+}
+
+fn download(name: &str, blob: Result<Blob, JsValue>) {
+    if let Ok(blob) = blob {
+        download_blob(name, &blob);
+    }
+}
+
+/// Has the browser save `blob` as a file called `name`
+pub fn download_blob(name: &str, blob: &Blob) {
+    let Ok(url) = Url::create_object_url_with_blob(blob) else {
+        return;
+    };
+    if let Ok(anchor) = leptos::prelude::document().create_element("a") {
+        let anchor = anchor.unchecked_into::<HtmlAnchorElement>();
+        anchor.set_href(&url);
+        anchor.set_download(name);
+        anchor.click();
+    }
+    set_timeout(
+        move || {
+            let _ = Url::revoke_object_url(&url);
+        },
+        REVOKE_AFTER,
+    );
+}
@@ -0,0 +1,265 @@
+use leptos::prelude::*;
+use web_sys::AudioContext;
+use web_time::Duration;
+
+use crate::{watchdog, Stepper};
+
+/// Flash lengths are set in steps of this many milliseconds
+const LENGTH_STEP_MS: u64 = 25;
+/// How long before a reset the warning is given
+pub const WARNING_LEAD: Duration = Duration::from_secs(1);
+/// Pitch and length of the warning tick
+const TICK_HZ: f32 = 1_000.0;
+const TICK_SECS: f64 = 0.05;
+/// Shown while the screen isn't flashing, fading back from a flash
+const IDLE_BORDER: &str = "border-ink transition-colors duration-400";
+const IDLE_BACKGROUND: &str = "bg-screen transition-colors duration-400";
+
+#[derive(Debug, Clone, Copy)]
+pub enum BlinkColor {
+    Orange,
+    Violet,
+    Green,
+    Sky,
+    Rose,
+    White,
+}
+
+impl BlinkColor {
+    pub const ALL: [BlinkColor; 6] = [
+        Self::Orange,
+        Self::Violet,
+        Self::Green,
+        Self::Sky,
+        Self::Rose,
+        Self::White,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Orange => "orange",
+            Self::Violet => "violet",
+            Self::Green => "green",
+            Self::Sky => "sky",
+            Self::Rose => "rose",
+            Self::White => "white",
+        }
+    }
+
+    fn border_class(&self) -> &'static str {
+        match self {
+            Self::Orange => "border-orange-400",
+            Self::Violet => "border-violet-400",
+            Self::Green => "border-green-400",
+            Self::Sky => "border-sky-400",
+            Self::Rose => "border-rose-400",
+            Self::White => "border-zinc-400",
+        }
+    }
+
+    fn background_class(&self) -> &'static str {
+        match self {
+            Self::Orange => "bg-orange-400",
+            Self::Violet => "bg-violet-400",
+            Self::Green => "bg-green-400",
+            Self::Sky => "bg-sky-400",
+            Self::Rose => "bg-rose-400",
+            Self::White => "bg-white",
+        }
+    }
+}
+
+/// What a flash is signalling
+#[derive(Debug, Clone, Copy)]
+pub enum Blink {
+    Tap,
+    Reset,
+}
+
+/// How the screen flashes on taps and resets
+#[derive(Clone, Copy)]
+pub struct Feedback {
+    /// 0 is off, 1 flashes the border and 2 the whole background
+    style: RwSignal<u64>,
+    /// Flash length, in steps of `LENGTH_STEP_MS`
+    length: RwSignal<u64>,
+    /// 1-based indices into `BlinkColor::ALL`
+    tap_color: RwSignal<u64>,
+    reset_color: RwSignal<u64>,
+    showing: RwSignal<Option<Blink>>,
+    /// Ends the flash showing, cleared when a new flash takes over
+    hide: StoredValue<Option<TimeoutHandle>>,
+    /// 0 is off, 1 turns the border yellow before a reset and 2 also plays a tick
+    warning: RwSignal<u64>,
+    warning_shown: RwSignal<bool>,
+    audio: StoredValue<Option<AudioContext>, LocalStorage>,
+    idle: Signal<bool>,
+}
+
+impl Feedback {
+    /// Flashes the screen for `blink`, unless flashes are turned off
+    pub fn blink(&self, blink: Blink) {
+        if self.style.get_untracked() == 0 || self.idle.get_untracked() {
+            return;
+        }
+        let showing = self.showing;
+        showing.set(Some(blink));
+        if let Some(hide) = self.hide.get_value() {
+            hide.clear();
+        }
+        let hide = set_timeout_with_handle(
+            move || showing.set(None),
+            Duration::from_millis(self.length.get_untracked() * LENGTH_STEP_MS),
+        );
+        self.hide.set_value(hide.ok());
+    }
+
+    /// Warns that the session is about to reset
+    pub fn warn(&self) {
+        match self.warning.get_untracked() {
+            0 => return,
+            2 => self.tick(),
+            _ => {}
+        }
+        self.warning_shown.set(true);
+    }
+
+    pub fn clear_warning(&self) {
+        if self.warning_shown.get_untracked() {
+            self.warning_shown.set(false);
+        }
+    }
+
+    /// Plays a short, quiet beep
+    fn tick(&self) {
+        if self.audio.with_value(Option::is_none) {
+            self.audio.set_value(AudioContext::new().ok());
+        }
+        self.audio.with_value(|context| {
+            let Some(context) = context else {
+                return;
+            };
+            let (Ok(oscillator), Ok(gain)) = (context.create_oscillator(), context.create_gain())
+            else {
+                return;
+            };
+            let now = context.current_time();
+            oscillator.frequency().set_value(TICK_HZ);
+            let _ = gain.gain().set_value_at_time(0.2, now);
+            let _ = gain
+                .gain()
+                .exponential_ramp_to_value_at_time(0.001, now + TICK_SECS);
+            let _ = oscillator.connect_with_audio_node(&gain);
+            let _ = gain.connect_with_audio_node(&context.destination());
+            let _ = oscillator.start();
+            let _ = oscillator.stop_with_when(now + TICK_SECS);
+        });
+    }
+
+    fn color(&self, blink: Blink) -> BlinkColor {
+        let index = match blink {
+            Blink::Tap => self.tap_color.get(),
+            Blink::Reset => self.reset_color.get(),
+        };
+        BlinkColor::ALL[index as usize - 1]
+    }
+
+    /// Border class for the flash currently showing
+    pub fn border_class(&self) -> &'static str {
+        match (self.style.get(), self.showing.get()) {
+            (1, Some(blink)) => self.color(blink).border_class(),
+            _ if self.warning_shown.get() && self.warning.get() > 0 => "border-yellow-400",
+            _ => IDLE_BORDER,
+        }
+    }
+
+    /// Background class for the flash currently showing
+    pub fn background_class(&self) -> &'static str {
+        match (self.style.get(), self.showing.get()) {
+            (2, Some(blink)) => self.color(blink).background_class(),
+            _ => IDLE_BACKGROUND,
+        }
+    }
+}
+
+pub fn use_feedback() -> Feedback {
+    Feedback {
+        style: RwSignal::new(1),
+        length: RwSignal::new(2),
+        tap_color: RwSignal::new(2),
+        reset_color: RwSignal::new(1),
+        showing: RwSignal::new(None),
+        hide: StoredValue::new(None),
+        warning: RwSignal::new(0),
+        warning_shown: RwSignal::new(false),
+        audio: StoredValue::new_local(None),
+        idle: watchdog::idle(),
+    }
+}
+
+#[component]
+pub fn FeedbackControls(feedback: Feedback) -> impl IntoView {
+    let color_name = move |index: RwSignal<u64>| {
+        Signal::derive(move || BlinkColor::ALL[index.get() as usize - 1].name().to_string())
+    };
+    let colors = BlinkColor::ALL.len() as u64;
+
+    view! {
+        <Stepper
+            label="blink"
+            value=feedback.style.read_only()
+            set_value=feedback.style.write_only()
+            min=0
+            max=2
+            description=Signal::derive(move || {
+                match feedback.style.get() {
+                    0 => "no flashes",
+                    1 => "flash the border",
+                    _ => "flash the background",
+                }
+                    .to_string()
+            })
+        />
+        <Stepper
+            label="blink-len"
+            value=feedback.length.read_only()
+            set_value=feedback.length.write_only()
+            min=1
+            max=9
+            description=Signal::derive(move || {
+                format!("{}ms per flash", feedback.length.get() * LENGTH_STEP_MS)
+            })
+        />
+        <Stepper
+            label="tap-color"
+            value=feedback.tap_color.read_only()
+            set_value=feedback.tap_color.write_only()
+            min=1
+            max=colors
+            description=color_name(feedback.tap_color)
+        />
+        <Stepper
+            label="reset-color"
+            value=feedback.reset_color.read_only()
+            set_value=feedback.reset_color.write_only()
+            min=1
+            max=colors
+            description=color_name(feedback.reset_color)
+        />
+        <Stepper
+            label="warn"
+            value=feedback.warning.read_only()
+            set_value=feedback.warning.write_only()
+            min=0
+            max=2
+            description=Signal::derive(move || {
+                match feedback.warning.get() {
+                    0 => "no warning before reset",
+                    1 => "yellow border at 1s left",
+                    _ => "yellow border and a tick",
+                }
+                    .to_string()
+            })
+        />
+    }
+}
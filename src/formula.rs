@@ -0,0 +1,378 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use leptos::prelude::*;
+use thiserror::Error;
+
+use crate::bpm::median;
+use crate::rate::format_rate;
+
+/// Values a formula can use: the number of taps, the ms from first to last, and the mean,
+/// median, shortest, longest and std dev of the intervals in ms
+const VARIABLES: [&str; 7] = ["n", "span", "mean", "median", "min", "max", "sd"];
+const FUNCTIONS: [&str; 5] = ["sqrt", "ln", "abs", "round", "floor"];
+/// Deepest formula parsed, counting each bracket, sign, power and each term chained onto a
+/// sum or product as a level, so evaluating and dropping one can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum FormulaError {
+    #[error("unexpected '{0}'")]
+    Unexpected(char),
+    #[error("formula ends too soon")]
+    UnexpectedEnd,
+    #[error("unknown name {0}")]
+    UnknownName(String),
+    #[error("formula is nested too deeply")]
+    TooDeep,
+}
+
+/// A parsed formula, which only does arithmetic on a fixed set of values so any input is
+/// safe to evaluate
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    Number(f64),
+    Variable(&'static str),
+    Function(&'static str, Box<Formula>),
+    Negate(Box<Formula>),
+    Binary(char, Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Formula, FormulaError> {
+        let mut parser = Parser {
+            chars: source.chars().peekable(),
+            depth: 0,
+        };
+        let formula = parser.sum()?;
+        match parser.next_token() {
+            None => Ok(formula),
+            Some(c) => Err(FormulaError::Unexpected(c)),
+        }
+    }
+
+    /// The formula's value for these taps, if it has one
+    pub fn evaluate(&self, offsets: &[u64]) -> Option<f64> {
+        if offsets.len() < 2 {
+            return None;
+        }
+        let value = self.value(&Values::new(offsets));
+        value.is_finite().then_some(value)
+    }
+
+    fn value(&self, values: &Values) -> f64 {
+        match self {
+            Self::Number(number) => *number,
+            Self::Variable(name) => values.get(name),
+            Self::Function(name, argument) => {
+                let argument = argument.value(values);
+                match *name {
+                    "sqrt" => argument.sqrt(),
+                    "ln" => argument.ln(),
+                    "abs" => argument.abs(),
+                    "round" => argument.round(),
+                    _ => argument.floor(),
+                }
+            }
+            Self::Negate(operand) => -operand.value(values),
+            Self::Binary(operator, left, right) => {
+                let (left, right) = (left.value(values), right.value(values));
+                match operator {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => left.powf(right),
+                }
+            }
+        }
+    }
+}
+
+struct Values {
+    n: f64,
+    span: f64,
+    intervals: Vec<f64>,
+}
+
+impl Values {
+    fn new(offsets: &[u64]) -> Self {
+        Values {
+            n: offsets.len() as f64,
+            span: (offsets[offsets.len() - 1] - offsets[0]) as f64,
+            intervals: offsets.windows(2).map(|w| (w[1] - w[0]) as f64).collect(),
+        }
+    }
+
+    fn get(&self, name: &str) -> f64 {
+        let mean = self.span / self.intervals.len() as f64;
+        let fold = |init: f64, f: fn(f64, f64) -> f64| self.intervals.iter().copied().fold(init, f);
+        match name {
+            "n" => self.n,
+            "span" => self.span,
+            "mean" => mean,
+            "median" => median(self.intervals.clone()),
+            "min" => fold(f64::INFINITY, f64::min),
+            "max" => fold(f64::NEG_INFINITY, f64::max),
+            _ => {
+                let squares: f64 = self.intervals.iter().map(|i| (i - mean).powi(2)).sum();
+                (squares / self.intervals.len() as f64).sqrt()
+            }
+        }
+    }
+}
+
+/// Recursive descent over `sum := product (+|- product)*`, `product := power (*|/ power)*`,
+/// `power := unary (^ power)?` and `unary := -unary | number | name | name(sum) | (sum)`
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn next_token(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.next()
+    }
+
+    fn peek_token(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn sum(&mut self) -> Result<Formula, FormulaError> {
+        let depth = self.depth;
+        let mut formula = self.product()?;
+        while let Some(operator @ ('+' | '-')) = self.peek_token() {
+            self.chars.next();
+            // each term nests the ones before it a level deeper
+            self.descend()?;
+            formula = Formula::Binary(operator, Box::new(formula), Box::new(self.product()?));
+        }
+        self.depth = depth;
+        Ok(formula)
+    }
+
+    fn product(&mut self) -> Result<Formula, FormulaError> {
+        let depth = self.depth;
+        let mut formula = self.power()?;
+        while let Some(operator @ ('*' | '/')) = self.peek_token() {
+            self.chars.next();
+            self.descend()?;
+            formula = Formula::Binary(operator, Box::new(formula), Box::new(self.power()?));
+        }
+        self.depth = depth;
+        Ok(formula)
+    }
+
+    /// Goes a level deeper, failing past `MAX_DEPTH`
+    fn descend(&mut self) -> Result<(), FormulaError> {
+        if self.depth == MAX_DEPTH {
+            return Err(FormulaError::TooDeep);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Runs `rule` one level deeper
+    fn nested(
+        &mut self,
+        rule: fn(&mut Self) -> Result<Formula, FormulaError>,
+    ) -> Result<Formula, FormulaError> {
+        self.descend()?;
+        let formula = rule(self);
+        self.depth -= 1;
+        formula
+    }
+
+    fn power(&mut self) -> Result<Formula, FormulaError> {
+        self.nested(Self::power_rule)
+    }
+
+    fn power_rule(&mut self) -> Result<Formula, FormulaError> {
+        let base = self.unary()?;
+        if self.peek_token() != Some('^') {
+            return Ok(base);
+        }
+        self.chars.next();
+        Ok(Formula::Binary(
+            '^',
+            Box::new(base),
+            Box::new(self.power()?),
+        ))
+    }
+
+    fn unary(&mut self) -> Result<Formula, FormulaError> {
+        self.nested(Self::unary_rule)
+    }
+
+    fn unary_rule(&mut self) -> Result<Formula, FormulaError> {
+        match self.next_token().ok_or(FormulaError::UnexpectedEnd)? {
+            '-' => Ok(Formula::Negate(Box::new(self.unary()?))),
+            '(' => {
+                let formula = self.sum()?;
+                match self.next_token() {
+                    Some(')') => Ok(formula),
+                    Some(c) => Err(FormulaError::Unexpected(c)),
+                    None => Err(FormulaError::UnexpectedEnd),
+                }
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                number
+                    .parse()
+                    .map(Formula::Number)
+                    .map_err(|_| FormulaError::Unexpected('.'))
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut name = c.to_string();
+                while let Some(c) = self.chars.next_if(char::is_ascii_alphanumeric) {
+                    name.push(c);
+                }
+                if let Some(variable) = VARIABLES.into_iter().find(|v| *v == name) {
+                    return Ok(Formula::Variable(variable));
+                }
+                let function = FUNCTIONS
+                    .into_iter()
+                    .find(|f| *f == name)
+                    .ok_or(FormulaError::UnknownName(name))?;
+                match self.next_token() {
+                    Some('(') => {}
+                    Some(c) => return Err(FormulaError::Unexpected(c)),
+                    None => return Err(FormulaError::UnexpectedEnd),
+                }
+                let argument = self.sum()?;
+                match self.next_token() {
+                    Some(')') => Ok(Formula::Function(function, Box::new(argument))),
+                    Some(c) => Err(FormulaError::Unexpected(c)),
+                    None => Err(FormulaError::UnexpectedEnd),
+                }
+            }
+            c => Err(FormulaError::Unexpected(c)),
+        }
+    }
+}
+
+/// A metric typed in as a formula over the taps, e.g. `60000 / median`
+#[component]
+pub fn FormulaMetric(offsets: Memo<Vec<u64>>, slow: ReadSignal<bool>) -> impl IntoView {
+    let (formula, set_formula) = signal(None::<Result<Formula, FormulaError>>);
+    let names = VARIABLES.join(" ");
+
+    view! {
+        <span class="text-label">"     formula:  "</span>
+        <input
+            class="bg-field outline-none w-[20ch] text-value"
+            placeholder="60000 / median"
+            on:keydown=move |evt| {
+                if evt.key() == "Enter" {
+                    let source = event_target_value(&evt);
+                    set_formula
+                        .set((!source.trim().is_empty()).then(|| Formula::parse(&source)));
+                }
+            }
+        />
+        <span class="text-comment">" # enter to set\n"</span>
+        {move || match formula.get() {
+            None => {
+                view! { <span class="text-comment">{format!("{:>14}{names}\n", "")}</span> }
+                    .into_any()
+            }
+            Some(Err(e)) => {
+                view! { <span class="text-comment">{format!("{:>14}{e}\n", "")}</span> }
+                    .into_any()
+            }
+            Some(Ok(formula)) => {
+                view! {
+                    <span class="text-label">{format!("{:>12}: ", "custom")}</span>
+                    <span class="text-value">
+                        {move || format_rate(formula.evaluate(&offsets.read()), slow.get())}
+                    </span>
+                    <span class="text-comment">"# the formula above\n"</span>
+                }
+                    .into_any()
+            }
+        }}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Intervals of 500, 500 and 600ms
+    const TAPS: [u64; 4] = [0, 500, 1000, 1600];
+
+    fn value(source: &str) -> Option<f64> {
+        Formula::parse(source).unwrap().evaluate(&TAPS)
+    }
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(value("1 + 2 * 3"), Some(7_f64));
+        assert_eq!(value("(1 + 2) * 3"), Some(9_f64));
+        assert_eq!(value("8 / 4 / 2"), Some(1_f64));
+        // powers group from the right
+        assert_eq!(value("2 ^ 3 ^ 2"), Some(512_f64));
+        // and a minus sign binds tighter than them
+        assert_eq!(value("-2 ^ 2"), Some(4_f64));
+        assert_eq!(value("2 * -3"), Some(-6_f64));
+        assert_eq!(value("--3"), Some(3_f64));
+    }
+
+    #[test]
+    fn reads_the_taps() {
+        assert_eq!(value("60000 / median"), Some(120_f64));
+        assert_eq!(value("n + span"), Some(1_604_f64));
+        assert_eq!(value("max - min"), Some(100_f64));
+        assert_eq!(value("round(sqrt(abs(-16)))"), Some(4_f64));
+    }
+
+    #[test]
+    fn rejects_bad_formulas() {
+        let parse = |source: &str| Formula::parse(source).unwrap_err();
+        assert_eq!(
+            parse("60000 / bpm"),
+            FormulaError::UnknownName("bpm".into())
+        );
+        assert_eq!(parse("(1 + 2"), FormulaError::UnexpectedEnd);
+        assert_eq!(parse("sqrt(4"), FormulaError::UnexpectedEnd);
+        assert_eq!(parse("1 +"), FormulaError::UnexpectedEnd);
+        assert_eq!(parse("sqrt 4"), FormulaError::Unexpected('4'));
+        assert_eq!(parse("1 2"), FormulaError::Unexpected('2'));
+        assert_eq!(parse("1 $ 2"), FormulaError::Unexpected('$'));
+    }
+
+    #[test]
+    fn stops_at_the_depth_limit() {
+        let parens = format!("{}1{}", "(".repeat(1000), ")".repeat(1000));
+        let negations = format!("{}1", "-".repeat(1000));
+        let powers = format!("{}2", "2^".repeat(1000));
+        let sums = format!("1{}", "+1".repeat(5000));
+        let products = format!("1{}", "*1".repeat(5000));
+        for source in [parens, negations, powers, sums, products] {
+            assert_eq!(Formula::parse(&source), Err(FormulaError::TooDeep));
+        }
+        let shallow = format!("{}1{}", "(".repeat(10), ")".repeat(10));
+        assert_eq!(Formula::parse(&shallow), Ok(Formula::Number(1_f64)));
+        let short = format!("1{}", "+1".repeat(10));
+        assert_eq!(
+            Formula::parse(&short).unwrap().evaluate(&TAPS),
+            Some(11_f64)
+        );
+    }
+
+    #[test]
+    fn evaluates_only_to_numbers() {
+        let formula = Formula::parse("60000 / mean").unwrap();
+        assert_eq!(formula.evaluate(&[]), None);
+        assert_eq!(formula.evaluate(&[1000]), None);
+        assert_eq!(formula.evaluate(&[0, 500]), Some(120_f64));
+        for source in ["1 / 0", "ln(0)", "sqrt(-1)", "60000 / (span - span)"] {
+            assert_eq!(value(source), None, "{source}");
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use leptos::prelude::*;
+use leptos_use::{use_raf_fn, UseRafFnCallbackArgs};
+
+use crate::{bpm, watchdog};
+
+/// Frames kept for estimating the refresh rate, a few seconds at common rates
+const WINDOW: usize = 480;
+/// The report is refreshed every this many frames rather than on each one
+const REPORT_EVERY: usize = 30;
+
+/// Refresh rate in frames per second and the standard deviation of frame intervals in ms
+#[derive(Clone, Copy)]
+struct FrameReport {
+    fps: f64,
+    jitter: f64,
+}
+
+fn report(frames: &VecDeque<f64>) -> Option<FrameReport> {
+    let first = *frames.front()?;
+    // frame timestamps are fed through the tap estimators like any other beats
+    let offsets: Vec<u64> = frames.iter().map(|t| (t - first).round() as u64).collect();
+    let fps = bpm::simple_regression(&offsets).ok()? / 60_f64;
+
+    let intervals: Vec<f64> = frames
+        .iter()
+        .zip(frames.iter().skip(1))
+        .map(|(a, b)| b - a)
+        .collect();
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance =
+        intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    Some(FrameReport {
+        fps,
+        jitter: variance.sqrt(),
+    })
+}
+
+/// Measures the display's effective refresh rate from animation frames while shown
+#[component]
+pub fn FrameRate() -> impl IntoView {
+    let latest = RwSignal::new(None::<FrameReport>);
+    let frames = RefCell::new(VecDeque::with_capacity(WINDOW));
+    let frame_count = Cell::new(0_usize);
+
+    let raf = use_raf_fn(move |UseRafFnCallbackArgs { timestamp, .. }| {
+        let mut frames = frames.borrow_mut();
+        if frames.len() == WINDOW {
+            frames.pop_front();
+        }
+        frames.push_back(timestamp);
+        frame_count.set(frame_count.get() + 1);
+        if frame_count.get().is_multiple_of(REPORT_EVERY) {
+            latest.set(report(&frames));
+        }
+    });
+
+    let idle = watchdog::idle();
+    Effect::new(move |_| {
+        if idle.get() {
+            (raf.pause)();
+        } else {
+            (raf.resume)();
+        }
+    });
+
+    let value = move |field: fn(FrameReport) -> f64| {
+        move || match latest.get() {
+            Some(report) => format!("{:6.2} ", field(report)),
+            None => "000.00 ".into(),
+        }
+    };
+
+    view! {
+        "\n"
+        <span class="text-label">{format!("{:>12}: ", "fps")}</span>
+        <span class="text-value">{value(|r| r.fps)}</span>
+        <span class="text-comment">"# lin-reg over animation frames\n"</span>
+        <span class="text-label">{format!("{:>12}: ", "jitter")}</span>
+        <span class="text-value">{value(|r| r.jitter)}</span>
+        <span class="text-comment">"# ms std dev of frame intervals\n"</span>
+    }
+}
@@ -0,0 +1,149 @@
+use leptos::prelude::*;
+
+use crate::TapData;
+
+/// Intervals each hand needs before its statistics are shown
+const MIN_INTERVALS: usize = 2;
+
+/// Intervals between taps that ended on one hand, in ms
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HandTiming {
+    mean: f64,
+    sd: f64,
+    count: usize,
+}
+
+fn timing(intervals: &[f64]) -> Option<HandTiming> {
+    if intervals.len() < MIN_INTERVALS {
+        return None;
+    }
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance =
+        intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    Some(HandTiming {
+        mean,
+        sd: variance.sqrt(),
+        count: intervals.len(),
+    })
+}
+
+/// Each hand's intervals, counting each interval towards the hand that ended it
+/// `hands` says which taps were left handed, and may fall short of the taps at the end
+fn split(offsets: &[u64], hands: &[bool]) -> [Vec<f64>; 2] {
+    let mut split = [Vec::new(), Vec::new()];
+    for (pair, &left) in offsets.windows(2).zip(&hands[1.min(hands.len())..]) {
+        split[left as usize].push((pair[1] - pair[0]) as f64);
+    }
+    split
+}
+
+/// How many ms left hand taps land after halfway between the right hand taps around them
+fn asymmetry(left: HandTiming, right: HandTiming) -> f64 {
+    (left.mean - right.mean) / 2_f64
+}
+
+/// The hand of each tap of the session, for alternating between left and right hand keys
+#[derive(Clone, Copy)]
+pub struct Hands {
+    /// True for the taps made with the left hand
+    hands: RwSignal<Vec<bool>>,
+}
+
+impl Hands {
+    /// Notes that the tap just made, the session's `taps`th, was made with the `left` hand
+    pub fn record(&self, left: bool, taps: usize) {
+        self.hands.update(|hands| {
+            // resets and undos leave hands for taps that are gone
+            hands.truncate(taps.saturating_sub(1));
+            // taps made some other way are taken to have alternated
+            while hands.len() + 1 < taps {
+                let next = hands.last().is_some_and(|&last| !last);
+                hands.push(next);
+            }
+            hands.push(left);
+        });
+    }
+}
+
+pub fn use_hands() -> Hands {
+    Hands {
+        hands: RwSignal::new(Vec::new()),
+    }
+}
+
+/// Timing of each hand while alternating, and which one lands early
+#[component]
+pub fn HandsTable(hands: Hands, tap_data: ReadSignal<TapData>) -> impl IntoView {
+    let timings = Memo::new(move |_| {
+        let offsets = tap_data.with(TapData::timestamps);
+        let [right, left] = hands.hands.with(|hands| split(&offsets, hands));
+        [timing(&left), timing(&right)]
+    });
+    let row = move |label: &'static str, hand: usize| {
+        view! {
+            <span class="text-label">{format!("{label:>12}: ")}</span>
+            {move || match timings.get()[hand] {
+                Some(HandTiming { mean, sd, count }) => {
+                    let spread = format!("# ms ±{sd:.1}, {count} taps\n");
+                    view! {
+                        <span class="text-value">{format!("{mean:6.1} ")}</span>
+                        <span class="text-comment">{spread}</span>
+                    }
+                        .into_any()
+                }
+                None => {
+                    view! {
+                        <span class="text-value">"  ---  "</span>
+                        <span class="text-comment">"# ms between taps\n"</span>
+                    }
+                        .into_any()
+                }
+            }}
+        }
+    };
+
+    view! {
+        "\n"
+        {row("left", 0)}
+        {row("right", 1)}
+        <span class="text-label">{format!("{:>12}: ", "asymmetry")}</span>
+        {move || match timings.get() {
+            [Some(left), Some(right)] => {
+                let late = asymmetry(left, right);
+                let side = if late < 0_f64 { "early" } else { "late" };
+                view! {
+                    <span class="text-value">{format!("{late:+6.1} ")}</span>
+                    <span class="text-comment">{format!("# ms left lands {side}\n")}</span>
+                }
+                    .into_any()
+            }
+            _ => {
+                view! {
+                    <span class="text-value">"  ---  "</span>
+                    <span class="text-comment">"# alternate left and right keys\n"</span>
+                }
+                    .into_any()
+            }
+        }}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_intervals_by_hand() {
+        // right, left, right, left, with the left hand 20ms late of halfway
+        let offsets = [0, 270, 500, 770, 1000];
+        let hands = [false, true, false, true, false];
+        let [right, left] = split(&offsets, &hands);
+        assert_eq!(left, vec![270_f64, 270_f64]);
+        assert_eq!(right, vec![230_f64, 230_f64]);
+        let (left, right) = (timing(&left).unwrap(), timing(&right).unwrap());
+        assert_eq!(asymmetry(left, right), 20_f64);
+        // hands that weren't recorded for the last taps leave their intervals out
+        let [right, left] = split(&offsets, &hands[..3]);
+        assert_eq!((left.len(), right.len()), (1, 1));
+    }
+}
@@ -0,0 +1,131 @@
+use leptos::prelude::*;
+use leptos_use::{use_interval_fn_with_options, UseIntervalFnOptions};
+
+use crate::pulse::BeatClock;
+use crate::{rate, watchdog, TapData};
+
+/// The page's title when there's no tempo to show
+const TITLE: &str = "bpm counter";
+/// A filled dot while a session is running, dimmed between beats, and a ring otherwise, as
+/// svg favicons
+const TAPPING_ICON: &str = "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' \
+    viewBox='0 0 16 16'><circle cx='8' cy='8' r='7' fill='%23e11d48'/></svg>";
+const OFFBEAT_ICON: &str = "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' \
+    viewBox='0 0 16 16'><circle cx='8' cy='8' r='5' fill='%23e11d48' \
+    fill-opacity='0.4'/></svg>";
+const IDLE_ICON: &str = "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' \
+    viewBox='0 0 16 16'><circle cx='8' cy='8' r='6' fill='none' stroke='%23888' \
+    stroke-width='2'/></svg>";
+/// Share of each beat the dot stays lit for
+const LIT_SHARE: f64 = 0.5;
+/// How often the favicon checks where it is in the beat
+const TICK_MS: u64 = 50;
+
+/// The page's title, with the tempo first so it shows in a narrow tab
+fn title(bpm: Option<f64>, tapping: bool, slow: bool) -> String {
+    let rate = bpm.map(|bpm| {
+        let rate = rate::format_rate(Some(bpm), slow);
+        match slow {
+            true => rate.trim().to_string(),
+            false => format!("{} bpm", rate.trim()),
+        }
+    });
+    match (rate, tapping) {
+        (Some(rate), true) => format!("{rate} — tapping"),
+        (None, true) => format!("tapping — {TITLE}"),
+        (Some(rate), false) => format!("{rate} — {TITLE}"),
+        (None, false) => TITLE.into(),
+    }
+}
+
+/// The favicon `beat` beats into a session, lit at the start of each beat, or steady
+/// before there's a grid to pulse on
+fn icon(beat: Option<f64>, tapping: bool) -> &'static str {
+    match (beat, tapping) {
+        (_, false) => IDLE_ICON,
+        (Some(beat), true) if beat.rem_euclid(1_f64) >= LIT_SHARE => OFFBEAT_ICON,
+        (_, true) => TAPPING_ICON,
+    }
+}
+
+/// Points the page's favicon at `href`, adding the link if there isn't one
+fn set_icon(href: &str) {
+    let document = document();
+    let link = match document.query_selector("link[rel='icon']") {
+        Ok(Some(link)) => link,
+        _ => {
+            let Ok(link) = document.create_element("link") else {
+                return;
+            };
+            let _ = link.set_attribute("rel", "icon");
+            let _ = document.head().map(|head| head.append_child(&link));
+            link
+        }
+    };
+    let _ = link.set_attribute("href", href);
+}
+
+/// Keeps the tab's title on the tempo and its icon pulsing on the beat of `clock` while a
+/// session is running, so both can be read from the tab strip while the
+/// window is in the background
+pub fn use_page_head(
+    bpm: Signal<Option<f64>>,
+    tap_data: ReadSignal<TapData>,
+    clock: BeatClock,
+    slow: ReadSignal<bool>,
+) {
+    let tapping = Memo::new(move |_| tap_data.read().start.is_some());
+    Effect::new(move |_| {
+        document().set_title(&title(bpm.get(), tapping.get(), slow.get()));
+    });
+
+    let shown = StoredValue::new(None::<&'static str>);
+    let show = move |href: &'static str| {
+        if shown.get_value() != Some(href) {
+            set_icon(href);
+            shown.set_value(Some(href));
+        }
+    };
+    let ticker = use_interval_fn_with_options(
+        move || {
+            let beat = clock.now().map(|(fit, elapsed_ms)| fit.beat_at(elapsed_ms));
+            show(icon(beat, true));
+        },
+        TICK_MS,
+        UseIntervalFnOptions {
+            immediate: false,
+            immediate_callback: false,
+        },
+    );
+    let idle = watchdog::idle();
+    Effect::new(move |_| {
+        let tapping = tapping.get();
+        if tapping && !idle.get() {
+            (ticker.resume)();
+        } else {
+            (ticker.pause)();
+            show(icon(None, tapping));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_the_session() {
+        assert_eq!(title(Some(128_f64), true, false), "128.00 bpm — tapping");
+        assert_eq!(title(None, true, false), "tapping — bpm counter");
+        assert_eq!(title(Some(12.4), false, true), "12/m — bpm counter");
+        assert_eq!(title(None, false, false), "bpm counter");
+    }
+
+    #[test]
+    fn pulses_the_icon() {
+        assert_eq!(icon(Some(3.2), true), TAPPING_ICON);
+        assert_eq!(icon(Some(3.7), true), OFFBEAT_ICON);
+        assert_eq!(icon(None, true), TAPPING_ICON);
+        assert_eq!(icon(Some(3.2), false), IDLE_ICON);
+    }
+}
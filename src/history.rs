@@ -0,0 +1,169 @@
+use web_time::{Duration, Instant};
+
+use crate::{bpm, TapData};
+
+/// Commands kept for undo, older ones are forgotten
+const MAX_DEPTH: usize = 256;
+
+/// A change to the tap session that can be undone and redone
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    Tap(Instant),
+    /// Ends the session, by hand or once the reset timeout runs out
+    Reset,
+    /// Deletes the tap at an index
+    Remove(usize),
+    /// Moves the tap at an index by some milliseconds
+    Nudge(usize, i64),
+    /// Puts the session back together with the one before it, as if no reset came between
+    Join,
+    /// Starts a new session at the tap at an index, leaving the taps before it as the last one
+    Split(usize),
+}
+
+/// What is needed to put the session back the way it was before a command
+enum Revert {
+    /// Drop the last timestamp
+    PopTap,
+    /// Continue the session that was reset
+    Resume(Instant),
+    /// Bring back a session the command replaced
+    Restore(Option<Instant>, Vec<u64>),
+}
+
+/// Commands that have been run and undone, most recent last
+#[derive(Default)]
+pub struct History {
+    done: Vec<(Command, Revert)>,
+    undone: Vec<Command>,
+}
+
+impl TapData {
+    /// Runs `command`, forgetting anything that was undone before it
+    pub fn run(&mut self, command: Command) {
+        self.history.undone.clear();
+        self.push(command);
+    }
+
+    /// Reverts the most recent command, returning false if there was none
+    pub fn undo(&mut self) -> bool {
+        let Some((command, revert)) = self.history.done.pop() else {
+            return false;
+        };
+        match revert {
+            Revert::PopTap => self.pop_tap(),
+            Revert::Resume(start) => self.start = Some(start),
+            Revert::Restore(start, timestamps) => {
+                self.start = start;
+                self.set_timestamps(&timestamps);
+            }
+        }
+        self.history.undone.push(command);
+        true
+    }
+
+    /// Runs the most recently undone command again, returning false if there was none
+    pub fn redo(&mut self) -> bool {
+        let Some(command) = self.history.undone.pop() else {
+            return false;
+        };
+        self.push(command);
+        true
+    }
+
+    /// The session the current one replaced when it began or was split from, and the
+    /// milliseconds from its last tap to the first tap of the current one
+    pub fn previous(&self) -> Option<(&[u64], u64)> {
+        let done = &self.history.done;
+        let index = done.iter().rposition(|entry| {
+            matches!(
+                entry,
+                (Command::Tap(_), Revert::Restore(None, _))
+                    | (Command::Join, _)
+                    | (Command::Split(_), _)
+            )
+        })?;
+        let (first, previous) = match &done[index] {
+            (Command::Tap(first), Revert::Restore(None, previous)) => (first, previous),
+            (Command::Split(at), Revert::Restore(_, whole)) => {
+                return Some((&whole[..*at], whole[*at] - whole[*at - 1]));
+            }
+            // already joined
+            _ => return None,
+        };
+        let last = *previous.last()?;
+        let gap = match index.checked_sub(1).map(|before| &done[before]) {
+            // the reset that ended it knows when it began
+            Some((Command::Reset, Revert::Resume(start))) => first
+                .duration_since(*start + Duration::from_millis(last))
+                .as_millis() as u64,
+            // otherwise the reset is taken to have come in place of a beat
+            _ if previous.len() > 1 => {
+                bpm::median(previous.windows(2).map(|w| (w[1] - w[0]) as f64).collect()) as u64
+            }
+            _ => 0,
+        };
+        Some((previous, gap))
+    }
+
+    fn push(&mut self, command: Command) {
+        let revert = match command {
+            Command::Tap(now) => {
+                let revert = match self.start {
+                    Some(_) => Revert::PopTap,
+                    // the first tap of a session replaces the last one
+                    None => Revert::Restore(None, self.timestamps()),
+                };
+                self.record(now);
+                revert
+            }
+            Command::Reset => match self.start.take() {
+                Some(start) => Revert::Resume(start),
+                // already reset, so there is nothing to undo
+                None => return,
+            },
+            Command::Remove(index) => {
+                let revert = Revert::Restore(self.start, self.timestamps());
+                self.remove_tap(index);
+                revert
+            }
+            Command::Nudge(index, ms) => {
+                let revert = Revert::Restore(self.start, self.timestamps());
+                self.nudge_tap(index, ms);
+                revert
+            }
+            Command::Join => {
+                let Some((previous, gap)) = self.previous() else {
+                    return;
+                };
+                let mut joined = previous.to_vec();
+                let revert = Revert::Restore(self.start, self.timestamps());
+                let shift = joined[joined.len() - 1] + gap;
+                joined.extend(self.offsets().map(|offset| offset + shift));
+                // a running session goes on from the start of the one it joins
+                self.start = self
+                    .start
+                    .and_then(|start| start.checked_sub(Duration::from_millis(shift)));
+                self.set_timestamps(&joined);
+                revert
+            }
+            Command::Split(at) => {
+                let timestamps = self.timestamps();
+                // splitting at either end leaves one of the sessions empty
+                if at == 0 || at >= timestamps.len() {
+                    return;
+                }
+                let first = timestamps[at];
+                let rest: Vec<u64> = timestamps[at..].iter().map(|t| t - first).collect();
+                let revert = Revert::Restore(self.start, timestamps);
+                self.start = self.start.map(|start| start + Duration::from_millis(first));
+                self.set_timestamps(&rest);
+                revert
+            }
+        };
+        if self.history.done.len() == MAX_DEPTH {
+            self.history.done.remove(0);
+        }
+        self.history.done.push((command, revert));
+    }
+}
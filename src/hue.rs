@@ -0,0 +1,167 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::{use_interval_fn_with_options, UseIntervalFnOptions};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::RequestInit;
+
+use crate::bpm::LineFit;
+use crate::pulse::BeatClock;
+use crate::{watchdog, Stepper, TapData};
+
+/// How often the beat grid is checked for a beat that just passed, in milliseconds
+const TICK_MS: u64 = 20;
+/// Tempos mapped onto the colour wheel, from blue at the slow end to red at the fast one
+const SLOW_BPM: f64 = 60.0;
+const FAST_BPM: f64 = 180.0;
+/// Hue of blue on the bridge's 0 to 65535 colour wheel
+const BLUE: f64 = 46_920.0;
+
+/// The number of the beat the grid is on `elapsed_ms` into the session
+fn beat_number(fit: &LineFit, elapsed_ms: f64) -> i64 {
+    fit.beat_at(elapsed_ms).floor() as i64
+}
+
+/// The light's colour for `bpm`, running from blue for slow tempos to red for fast ones
+fn hue_of(bpm: f64) -> u16 {
+    let fast = ((bpm - SLOW_BPM) / (FAST_BPM - SLOW_BPM)).clamp(0_f64, 1_f64);
+    (BLUE * (1_f64 - fast)).round() as u16
+}
+
+/// A light on a Hue bridge on the local network, pulsed on each beat of the estimated grid
+#[derive(Clone, Copy)]
+pub struct HueLights {
+    bridge: RwSignal<Option<String>>,
+    user: RwSignal<Option<String>>,
+    /// 0 is off
+    light: RwSignal<u64>,
+    error: RwSignal<Option<String>>,
+}
+
+impl HueLights {
+    fn pulse(&self, bpm: f64) {
+        let (Some(bridge), Some(user)) = (self.bridge.get_untracked(), self.user.get_untracked())
+        else {
+            return;
+        };
+        // plain http from an https page is blocked as mixed content
+        let url = format!(
+            "https://{bridge}/api/{user}/lights/{}/state",
+            self.light.get_untracked()
+        );
+        // one breathe cycle a beat stays within the bridge's limit of ten commands a second
+        let body = format!(
+            r#"{{"on": true, "hue": {}, "sat": 254, "alert": "select"}}"#,
+            hue_of(bpm)
+        );
+        let error = self.error;
+        spawn_local(async move {
+            let init = RequestInit::new();
+            init.set_method("PUT");
+            init.set_body(&JsValue::from_str(&body));
+            let sent = JsFuture::from(window().fetch_with_str_and_init(&url, &init)).await;
+            error.set(sent.err().map(|_| "couldn't reach the bridge".into()));
+        });
+    }
+}
+
+/// Pulses the chosen light on the beat of `clock` while a session is running
+pub fn use_hue_lights(tap_data: ReadSignal<TapData>, clock: BeatClock) -> HueLights {
+    let hue = HueLights {
+        bridge: RwSignal::new(None),
+        user: RwSignal::new(None),
+        light: RwSignal::new(0),
+        error: RwSignal::new(None),
+    };
+    let last_beat = StoredValue::new(None::<i64>);
+
+    let ticker = use_interval_fn_with_options(
+        move || {
+            let Some((fit, elapsed_ms)) = clock.now() else {
+                return;
+            };
+            let beat = beat_number(&fit, elapsed_ms);
+            if last_beat.get_value().is_some_and(|last| last != beat) {
+                hue.pulse(fit.bpm());
+            }
+            last_beat.set_value(Some(beat));
+        },
+        TICK_MS,
+        UseIntervalFnOptions {
+            immediate: false,
+            immediate_callback: false,
+        },
+    );
+    let running = Memo::new(move |_| tap_data.read().start.is_some());
+    let idle = watchdog::idle();
+    Effect::new(move |_| {
+        if hue.light.get() != 0 && running.get() && !idle.get() {
+            (ticker.resume)();
+        } else {
+            (ticker.pause)();
+            // the next session's first beat starts the pulses afresh
+            last_beat.set_value(None);
+        }
+    });
+
+    hue
+}
+
+#[component]
+pub fn HueControls(hue: HueLights) -> impl IntoView {
+    let input = move |setting: RwSignal<Option<String>>, placeholder: &'static str| {
+        view! {
+            <input
+                class="bg-field outline-none w-[12ch] text-value"
+                placeholder=placeholder
+                on:change=move |evt| {
+                    let value = event_target_value(&evt).trim().to_string();
+                    setting.set(Some(value).filter(|value| !value.is_empty()));
+                }
+            />
+        }
+    };
+    let status = move || match (hue.light.get(), hue.error.get()) {
+        (0, _) => "light pulsed on the beat, 0 is off".to_string(),
+        (_, Some(error)) => error,
+        (light, None) => format!("light {light} pulses while tapping"),
+    };
+
+    view! {
+        <span class="text-label">"  hue-bridge:  "</span>
+        {input(hue.bridge, "ip")}
+        // the bridge's certificate is its own, which the browser has to be told to trust
+        <span class="text-comment">" # open https://ip once to trust it\n"</span>
+        <span class="text-label">"    hue-user:  "</span>
+        {input(hue.user, "username")}
+        <span class="text-comment">" # made by pressing the bridge's link button\n"</span>
+        <Stepper
+            label="hue-light"
+            value=hue.light.read_only()
+            set_value=hue.light.write_only()
+            min=0
+            max=50
+            description=Signal::derive(status)
+        />
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_the_beat_and_tempo() {
+        // 120 bpm with the first beat at 250ms
+        let fit = LineFit {
+            slope: 1_f64 / 500_f64,
+            intercept: -0.5,
+        };
+        assert_eq!(beat_number(&fit, 0_f64), -1);
+        assert_eq!(beat_number(&fit, 250_f64), 0);
+        assert_eq!(beat_number(&fit, 1_249_f64), 1);
+        assert_eq!(hue_of(40_f64), 46_920);
+        assert_eq!(hue_of(120_f64), 23_460);
+        assert_eq!(hue_of(200_f64), 0);
+    }
+}
@@ -0,0 +1,365 @@
+use itertools::Itertools;
+use js_sys::{Array, Function, Reflect, Uint8Array};
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, File, HtmlInputElement, Response};
+
+use crate::zip::{self, Data};
+use crate::{bpm, export, session, Metric, TapData};
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
+pub enum ImportError {
+    #[error("file could not be read")]
+    Unreadable,
+    #[error("no onset times found")]
+    Empty,
+    #[error("json isn't an array of seconds")]
+    NotSeconds,
+    #[error("onsets over 49 days apart")]
+    TooFarApart,
+}
+
+/// Header names of the column onset times are read from, up to the first character that
+/// isn't a letter or digit so `time (s)` and `onset_time` count too
+const TIME_COLUMNS: [&str; 5] = ["time", "start", "onset", "location", "position"];
+
+/// The fields of a CSV line, unquoted
+fn fields(line: &str) -> impl Iterator<Item = &str> {
+    line.split([',', ';', '\t'])
+        .map(|field| field.trim().trim_matches('"').trim())
+}
+
+/// A time in seconds, written as seconds or as `m:ss.sss` or `h:mm:ss.sss`
+fn seconds(field: &str) -> Option<f64> {
+    field.split(':').try_fold(0_f64, |total, part| {
+        Some(total * 60_f64 + part.parse::<f64>().ok()?)
+    })
+}
+
+fn is_time_column(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let name = name.split(|c: char| !c.is_ascii_alphanumeric()).next();
+    name.is_some_and(|name| TIME_COLUMNS.contains(&name))
+}
+
+/// Reads onset times in seconds, from a JSON array or a CSV, and returns them as
+/// millisecond offsets from the first onset
+///
+/// CSVs are read from the column headed with a time name, like the location column of a
+/// marker list, or from the last number on each line if there's no such header, so an
+/// index column before the times or a headerless list of onsets from librosa load as they
+/// are
+pub fn parse_onsets(text: &str) -> Result<Vec<u64>, ImportError> {
+    let text = text.trim();
+    let seconds: Vec<f64> = if text.starts_with('[') {
+        serde_json::from_str(text).map_err(|_| ImportError::NotSeconds)?
+    } else {
+        let mut lines = text.lines();
+        // lines before the header are titles, the column is found on the header itself
+        let column = lines
+            .by_ref()
+            .find_map(|line| fields(line).position(is_time_column));
+        let lines = match column {
+            Some(_) => lines,
+            None => text.lines(),
+        };
+        lines
+            .filter_map(|line| match column {
+                Some(column) => fields(line).nth(column).and_then(seconds),
+                None => fields(line).filter_map(seconds).last(),
+            })
+            .collect()
+    };
+    let mut millis: Vec<u64> = seconds
+        .into_iter()
+        .filter(|s: &f64| s.is_finite() && *s >= 0_f64)
+        .map(|s| (s * 1_000_f64).round() as u64)
+        .collect();
+    millis.sort_unstable();
+    let first = *millis.first().ok_or(ImportError::Empty)?;
+    if !session::fits(&millis) {
+        return Err(ImportError::TooFarApart);
+    }
+    Ok(millis.into_iter().map(|ms| ms - first).collect())
+}
+
+/// A file picked for import and the onsets read from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedFile {
+    pub name: String,
+    pub onsets: Result<Vec<u64>, ImportError>,
+}
+
+/// Inflates raw deflate `data` with the browser's DecompressionStream, which web-sys only
+/// binds behind its unstable apis
+async fn inflate(data: &[u8]) -> Option<String> {
+    let class = Reflect::get(&window(), &JsValue::from_str("DecompressionStream")).ok()?;
+    let format = Array::of1(&JsValue::from_str("deflate-raw"));
+    let stream = Reflect::construct(class.dyn_ref::<Function>()?, &format).ok()?;
+    let blob = Blob::new_with_u8_array_sequence(&Array::of1(&Uint8Array::from(data))).ok()?;
+    let inflated = blob.stream().pipe_through(stream.unchecked_ref());
+    let response = Response::new_with_opt_readable_stream(Some(&inflated)).ok()?;
+    JsFuture::from(response.text().ok()?)
+        .await
+        .ok()?
+        .as_string()
+}
+
+/// The onsets of each csv, json and txt file in the zip archive `file`
+async fn read_zip(file: &File) -> Vec<ImportedFile> {
+    let bytes = JsFuture::from(file.array_buffer()).await;
+    let bytes = bytes.map_or_else(|_| Vec::new(), |buffer| Uint8Array::new(&buffer).to_vec());
+    let Some(entries) = zip::entries(&bytes) else {
+        return vec![ImportedFile {
+            name: file.name(),
+            onsets: Err(ImportError::Unreadable),
+        }];
+    };
+    let mut imported = Vec::new();
+    for entry in entries {
+        let name = entry.name.rsplit('/').next().unwrap_or_default();
+        let extension = name.rsplit_once('.').map(|(_, e)| e.to_lowercase());
+        let listed = matches!(extension.as_deref(), Some("csv" | "json" | "txt"));
+        // folders zipped on a mac carry a resource fork for each file
+        if !listed || entry.name.starts_with("__MACOSX/") {
+            continue;
+        }
+        let text = match entry.data {
+            Data::Stored(data) => String::from_utf8(data.to_vec()).ok(),
+            Data::Deflated(data) => inflate(data).await,
+            Data::Unsupported => None,
+        };
+        imported.push(ImportedFile {
+            name: name.to_string(),
+            onsets: text
+                .ok_or(ImportError::Unreadable)
+                .and_then(|text| parse_onsets(&text)),
+        });
+    }
+    imported
+}
+
+async fn read_files(input: HtmlInputElement) -> Vec<ImportedFile> {
+    let Some(list) = input.files() else {
+        return Vec::new();
+    };
+    let files: Vec<File> = (0..list.length()).filter_map(|i| list.get(i)).collect();
+    // lets the same files be chosen again after they change
+    input.set_value("");
+    let mut imported = Vec::new();
+    for file in files {
+        if file.name().to_lowercase().ends_with(".zip") {
+            imported.extend(read_zip(&file).await);
+            continue;
+        }
+        let text = JsFuture::from(file.text())
+            .await
+            .ok()
+            .and_then(|t| t.as_string());
+        imported.push(ImportedFile {
+            name: file.name(),
+            onsets: text
+                .ok_or(ImportError::Unreadable)
+                .and_then(|text| parse_onsets(&text)),
+        });
+    }
+    imported
+}
+
+/// Every estimate for every file, one row per file
+fn results_csv(files: &[ImportedFile]) -> String {
+    let mut csv = format!(
+        "file,taps,{}\n",
+        Metric::ALL.iter().map(Metric::label).join(",")
+    );
+    for file in files {
+        let onsets = file.onsets.as_deref().unwrap_or_default();
+        let estimates = Metric::ALL
+            .iter()
+            .map(|metric| match metric.estimate(onsets) {
+                Ok(bpm) => format!("{bpm:.3}"),
+                Err(_) => String::new(),
+            })
+            .join(",");
+        let name = file.name.replace('"', "\"\"");
+        csv.push_str(&format!("\"{name}\",{},{estimates}\n", onsets.len()));
+    }
+    csv
+}
+
+/// Result row for one file of a batch
+fn file_row(file: ImportedFile) -> impl IntoView {
+    let stem = file
+        .name
+        .rsplit_once('.')
+        .map_or(file.name.as_str(), |(stem, _)| stem);
+    let label = format!("{:>12}: ", stem.chars().take(12).collect::<String>());
+    let (bpm, note) = match &file.onsets {
+        Ok(onsets) => (
+            bpm::thiel_sen(onsets).ok(),
+            format!("{} taps", onsets.len()),
+        ),
+        Err(e) => (None, e.to_string()),
+    };
+    view! {
+        <span class="text-label">{label}</span>
+        <span class="text-value">
+            {bpm.map_or("000.00 ".into(), |bpm| format!("{bpm:6.2} "))}
+        </span>
+        <span class="text-comment">"# "{note}"\n"</span>
+    }
+}
+
+/// Loads a file of onset times recorded elsewhere as the session, or estimates the tempo of
+/// each of several files, or of each file zipped together, at once
+#[component]
+pub fn Import(set_tap_data: WriteSignal<TapData>) -> impl IntoView {
+    let import = Action::new_local(move |input: &HtmlInputElement| {
+        let input = input.clone();
+        async move {
+            let files = read_files(input).await;
+            if let [ImportedFile {
+                onsets: Ok(onsets), ..
+            }] = files.as_slice()
+            {
+                set_tap_data.write().load(onsets.clone());
+            }
+            files
+        }
+    });
+    let export = move |_| {
+        import.value().with(|files| {
+            if let Some(files) = files {
+                export::download_text("bpm-results.csv", "text/csv", &results_csv(files));
+            }
+        })
+    };
+    let status = move || {
+        if import.pending().get() {
+            return "reading".into_any();
+        }
+        import.value().with(|files| match files.as_deref() {
+            None | Some([]) => "csv, json or zip, in seconds".into_any(),
+            Some([file]) => match &file.onsets {
+                Ok(onsets) => format!("loaded {} onsets", onsets.len()).into_any(),
+                Err(e) => e.to_string().into_any(),
+            },
+            Some(files) => view! {
+                {format!("{} files, ", files.len())}
+                <button class="hover:text-value" on:mousedown=export>
+                    "export"
+                </button>
+            }
+            .into_any(),
+        })
+    };
+    let results = move || {
+        import.value().with(|files| match files.as_deref() {
+            Some(files) if files.len() > 1 => files
+                .iter()
+                .cloned()
+                .map(file_row)
+                .collect_view()
+                .into_any(),
+            _ => ().into_any(),
+        })
+    };
+
+    view! {
+        <span class="text-label">"      import:  "</span>
+        <label class="hover:text-value cursor-pointer">
+            "open "
+            <input
+                type="file"
+                accept=".csv,.json,.txt,.zip"
+                multiple
+                class="hidden"
+                on:change=move |evt| {
+                    import.dispatch_local(event_target::<HtmlInputElement>(&evt));
+                }
+            />
+        </label>
+        <span class="text-comment">" # "{status}"\n"</span>
+        {results}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_csv_onsets() {
+        let csv = "time,label\n1.5,kick\n\"0.5\",snare\n1.0;hat\nend\n";
+        assert_eq!(parse_onsets(csv).unwrap(), vec![0, 500, 1000]);
+        assert!(matches!(parse_onsets("time\n"), Err(ImportError::Empty)));
+        // without a header the times are the last number on each line, after any index
+        assert_eq!(parse_onsets("1,0.5\n2,1.0\n").unwrap(), vec![0, 500]);
+    }
+
+    #[test]
+    fn reads_marker_exports() {
+        // a pro tools memory location listing, numbered before the location
+        let pro_tools = "MARKERS LISTING\n\
+            #   \tLOCATION     \tTIME REFERENCE\tUNITS    \tNAME\n\
+            1   \t0:01.000     \t44100         \tSamples  \tVerse\n\
+            2   \t0:01.500     \t66150         \tSamples  \tMarker 2\n\
+            3   \t1:02.000     \t2734200       \tSamples  \tChorus\n";
+        assert_eq!(parse_onsets(pro_tools).unwrap(), vec![0, 500, 61_000]);
+        // a reaper region/marker manager export, timed in minutes and seconds
+        let reaper = "#,Name,Start,End,Length\n\
+            M1,Intro,0:00.250,,\n\
+            R1,Verse,0:00.750,0:10.000,0:09.250\n";
+        assert_eq!(parse_onsets(reaper).unwrap(), vec![0, 500]);
+        let titled = "time (s),label\n0.5,a\n1,b\n";
+        assert_eq!(parse_onsets(titled).unwrap(), vec![0, 500]);
+    }
+
+    #[test]
+    fn exports_every_estimate() {
+        let files = [
+            ImportedFile {
+                name: "steady.csv".into(),
+                onsets: Ok(vec![0, 500, 1000, 1500]),
+            },
+            ImportedFile {
+                name: "a \"b\".csv".into(),
+                onsets: Err(ImportError::Empty),
+            },
+        ];
+        let csv = results_csv(&files);
+        let lines: Vec<&str> = csv.lines().collect();
+        let labels = Metric::ALL.iter().map(Metric::label).join(",");
+        assert_eq!(lines[0], format!("file,taps,{labels}"));
+        let estimates = vec!["120.000"; Metric::ALL.len()].join(",");
+        assert_eq!(lines[1], format!("\"steady.csv\",4,{estimates}"));
+        // files that didn't read leave their estimates empty, with quotes doubled in the name
+        let blanks = ",".repeat(Metric::ALL.len() - 1);
+        assert_eq!(lines[2], format!("\"a \"\"b\"\".csv\",0,{blanks}"));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn reads_json_onsets() {
+        assert_eq!(
+            parse_onsets(" [0.25, 0.75, 1.25] ").unwrap(),
+            vec![0, 500, 1000]
+        );
+        // negative times aren't onsets
+        assert_eq!(parse_onsets("[-1, 0, 0.5]").unwrap(), vec![0, 500]);
+        assert!(matches!(parse_onsets("[]"), Err(ImportError::Empty)));
+        assert!(matches!(
+            parse_onsets("[0, 5000000]"),
+            Err(ImportError::TooFarApart)
+        ));
+        for json in [r#"[{"time": 0.5}]"#, "[[0.5, 1.0]]", r#"["0.5"]"#, "[0.5,"] {
+            assert!(
+                matches!(parse_onsets(json), Err(ImportError::NotSeconds)),
+                "read {json}"
+            );
+        }
+    }
+}
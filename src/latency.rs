@@ -0,0 +1,253 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use leptos::prelude::*;
+use leptos_use::{use_raf_fn_with_options, UseRafFnCallbackArgs, UseRafFnOptions};
+use serde::{Deserialize, Serialize};
+use web_time::{Duration, Instant};
+
+use crate::autosave::storage;
+use crate::bpm::median;
+use crate::{schema, watchdog};
+
+/// Tempo the screen flashes at while testing
+const TEST_BPM: f64 = 100.0;
+/// Taps needed before a latency is reported
+const MIN_TAPS: usize = 8;
+const PROFILE_KEY: &str = "bpm-profile";
+/// Where this browser keeps the name of the device it runs on
+const DEVICE_KEY: &str = "bpm-device";
+
+/// Settings kept per device, which travel with an exported archive to other browsers
+#[derive(Serialize, Deserialize, Default)]
+struct Profile {
+    /// Measured latency in milliseconds, keyed by the device's name
+    latency_ms: BTreeMap<String, f64>,
+}
+
+/// A name for the device made from its platform and whether it's tapped by touch, neither
+/// of which changes as the browser updates
+fn default_device() -> String {
+    let navigator = window().navigator();
+    let platform = navigator.platform().unwrap_or_default();
+    let input = if navigator.max_touch_points() > 0 {
+        "touch"
+    } else {
+        "mouse"
+    };
+    format!("{platform} {input}").trim_start().to_string()
+}
+
+/// The name this browser was given for its device, or else the one made up for it
+fn device() -> String {
+    storage()
+        .and_then(|storage| storage.get_item(DEVICE_KEY).ok().flatten())
+        .filter(|device| !device.is_empty())
+        .unwrap_or_else(default_device)
+}
+
+fn save_device(device: &str) {
+    if let Some(storage) = storage() {
+        let _ = storage.set_item(DEVICE_KEY, device);
+    }
+}
+
+fn load_profile() -> Profile {
+    storage()
+        .and_then(|storage| storage.get_item(PROFILE_KEY).ok().flatten())
+        .and_then(|saved| schema::from_json(&saved).ok())
+        .unwrap_or_default()
+}
+
+/// The latency measured on `device` by an earlier test, if any
+fn saved_calibration(device: &str) -> Option<f64> {
+    load_profile().latency_ms.remove(device)
+}
+
+/// When a tap registered at `at` was made, `latency_ms` earlier
+fn aimed_at(at: Instant, latency_ms: f64) -> Instant {
+    let latency = Duration::from_secs_f64(latency_ms.abs() / 1_000_f64);
+    if latency_ms >= 0_f64 {
+        at.checked_sub(latency).unwrap_or(at)
+    } else {
+        at + latency
+    }
+}
+
+fn save_calibration(device: &str, latency: f64) {
+    let mut profile = load_profile();
+    profile.latency_ms.insert(device.to_string(), latency);
+    if let Some(storage) = storage() {
+        let _ = storage.set_item(PROFILE_KEY, &schema::to_json(&profile));
+    }
+}
+
+/// Flashes the screen at a known tempo and measures how far behind it the taps land,
+/// which is the combined display and input latency of the setup
+#[derive(Clone, Copy)]
+pub struct LatencyTest {
+    running: RwSignal<bool>,
+    /// Milliseconds (performance.now) at which the most recent flash was drawn
+    last_flash: RwSignal<Option<f64>>,
+    /// Milliseconds between each tap and the flash it was aimed at
+    offsets: RwSignal<Vec<f64>>,
+    /// Name of the device calibrations are kept under
+    device: RwSignal<String>,
+    /// Latency of this device, from the last test that measured one
+    calibration: RwSignal<Option<f64>>,
+}
+
+impl LatencyTest {
+    pub fn is_running(&self) -> bool {
+        self.running.get_untracked()
+    }
+
+    /// Milliseconds taps on this device land late by, 0 until it's been measured
+    pub fn calibration(&self) -> f64 {
+        self.calibration.get_untracked().unwrap_or_default()
+    }
+
+    /// When a tap registered at `at` was made, taking off this device's latency
+    pub fn tapped_at(&self, at: Instant) -> Instant {
+        aimed_at(at, self.calibration())
+    }
+
+    /// Renames the device, switching to the calibration kept under the new name
+    fn set_device(&self, device: String) {
+        save_device(&device);
+        let device = if device.is_empty() {
+            default_device()
+        } else {
+            device
+        };
+        self.calibration.set(saved_calibration(&device));
+        self.device.set(device);
+    }
+
+    /// Starts or stops the test, keeping the latency it measured when stopped
+    pub fn toggle(&self) {
+        if self.running.get_untracked() {
+            if let Some((latency, _)) = self.summary() {
+                save_calibration(&self.device.get_untracked(), latency);
+                self.calibration.set(Some(latency));
+            }
+        }
+        self.offsets.write().clear();
+        self.last_flash.set(None);
+        self.running.update(|running| *running = !*running);
+    }
+
+    /// Records a tap made at `time_ms`, matched to whichever flash is nearest
+    pub fn record_tap(&self, time_ms: f64) {
+        let Some(flash) = self.last_flash.get_untracked() else {
+            return;
+        };
+        let period = 60_000_f64 / TEST_BPM;
+        let mut offset = time_ms - flash;
+        // taps made in anticipation of the next flash are early, not very late
+        if offset > period / 2_f64 {
+            offset -= period;
+        }
+        self.offsets.write().push(offset);
+    }
+
+    /// Median offset and median absolute deviation, once enough taps are in
+    fn summary(&self) -> Option<(f64, f64)> {
+        let offsets = self.offsets.read();
+        if offsets.len() < MIN_TAPS {
+            return None;
+        }
+        let latency = median(offsets.clone());
+        let spread = median(offsets.iter().map(|o| (o - latency).abs()).collect());
+        Some((latency, spread))
+    }
+}
+
+/// Sets up the latency test, calling `flash` on every beat while it runs
+pub fn use_latency_test(flash: impl Fn() + 'static) -> LatencyTest {
+    let device = device();
+    let test = LatencyTest {
+        running: RwSignal::new(false),
+        last_flash: RwSignal::new(None),
+        offsets: RwSignal::new(Vec::new()),
+        calibration: RwSignal::new(saved_calibration(&device)),
+        device: RwSignal::new(device),
+    };
+
+    let started_at = Rc::new(Cell::new(None::<f64>));
+    let raf = {
+        let started_at = Rc::clone(&started_at);
+        use_raf_fn_with_options(
+            move |UseRafFnCallbackArgs { timestamp, .. }| {
+                let start = started_at.get().unwrap_or(timestamp);
+                started_at.set(Some(start));
+                let period = 60_000_f64 / TEST_BPM;
+                let beat = ((timestamp - start) / period).floor();
+                let flashed = test.last_flash.get_untracked();
+                // flash on the first frame drawn after each beat is due
+                if flashed.is_none_or(|last| ((last - start) / period).floor() < beat) {
+                    test.last_flash.set(Some(timestamp));
+                    flash();
+                }
+            },
+            UseRafFnOptions::default().immediate(false),
+        )
+    };
+
+    let idle = watchdog::idle();
+    Effect::new(move |_| {
+        if test.running.get() && !idle.get() {
+            (raf.resume)();
+        } else {
+            (raf.pause)();
+            started_at.set(None);
+        }
+    });
+
+    test
+}
+
+#[component]
+pub fn LatencyControls(test: LatencyTest) -> impl IntoView {
+    let status = move || {
+        if !test.running.get() {
+            return match test.calibration.get() {
+                Some(latency) => format!("{latency:+.0}ms, taken off each tap"),
+                None => "tap along to the flashes".into(),
+            };
+        }
+        match test.summary() {
+            Some((latency, spread)) => format!("{latency:+.0}ms ±{spread:.0}ms"),
+            None => format!("{}/{MIN_TAPS} taps to measure", test.offsets.read().len()),
+        }
+    };
+
+    view! {
+        <span class="text-label">"     latency:  "</span>
+        <button class="hover:text-value" on:mousedown=move |_| test.toggle()>
+            {move || if test.running.get() { "stop " } else { "start" }}
+        </button>
+        <span class="text-comment">" # "{status}"\n"</span>
+        <span class="text-label">"      device:  "</span>
+        <input
+            class="bg-field outline-none w-[16ch] text-value"
+            prop:value=move || test.device.get()
+            on:change=move |evt| test.set_device(event_target_value(&evt).trim().to_string())
+        />
+        <span class="text-comment">" # latency is kept per device\n"</span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_off_the_latency() {
+        let at = Instant::now() + Duration::from_secs(1);
+        assert_eq!(aimed_at(at, 40_f64), at - Duration::from_millis(40));
+        assert_eq!(aimed_at(at, -12.5), at + Duration::from_micros(12_500));
+        assert_eq!(aimed_at(at, 0_f64), at);
+    }
+}
@@ -0,0 +1,199 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use js_sys::Reflect;
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AbortSignal, RequestInit, Response};
+
+use crate::autosave;
+
+/// Number of search results whose tempo is looked up
+const MAX_RESULTS: usize = 3;
+/// How long a catalog has to answer before the lookup gives up on it, in milliseconds
+const TIMEOUT_MS: u32 = 10_000;
+/// Where the proxy url is remembered between visits
+const PROXY_KEY: &str = "bpm-lookup-proxy";
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
+pub enum LookupError {
+    #[error("catalog could not be reached")]
+    Unreachable,
+    #[error("catalog sent an unexpected response")]
+    Malformed,
+    #[error("catalog needs a cors proxy")]
+    NoProxy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogTrack {
+    pub artist: String,
+    pub title: String,
+    /// Published tempo, if the catalog knows it
+    pub bpm: Option<f64>,
+}
+
+pub type LookupFuture = Pin<Box<dyn Future<Output = Result<Vec<CatalogTrack>, LookupError>>>>;
+
+/// A music catalog that publishes song tempos
+pub trait TempoProvider {
+    fn name(&self) -> &'static str;
+    /// Searches for songs matching `query`, best match first, with `proxy` put in front of
+    /// the urls of catalogs that send no CORS headers
+    fn search(&self, query: &str, proxy: &str) -> LookupFuture;
+}
+
+/// The public Deezer API, which needs no key and reports a `bpm` per track, but sends no
+/// CORS headers so is only reachable through a proxy
+pub struct Deezer;
+
+impl TempoProvider for Deezer {
+    fn name(&self) -> &'static str {
+        "deezer"
+    }
+
+    fn search(&self, query: &str, proxy: &str) -> LookupFuture {
+        let url = format!(
+            "{proxy}https://api.deezer.com/search?q={}&limit={MAX_RESULTS}",
+            js_sys::encode_uri_component(query)
+        );
+        let proxy = proxy.to_string();
+        Box::pin(async move {
+            if proxy.is_empty() {
+                return Err(LookupError::NoProxy);
+            }
+            let found = fetch_json(&url).await?;
+            let data = get(&found, "data")?.dyn_into::<js_sys::Array>();
+            let data = data.map_err(|_| LookupError::Malformed)?;
+            let mut tracks = Vec::new();
+            // search results leave out the tempo, so each track is fetched for it
+            for result in data.iter().take(MAX_RESULTS) {
+                let id = get(&result, "id")?.as_f64().ok_or(LookupError::Malformed)?;
+                let url = format!("{proxy}https://api.deezer.com/track/{id}");
+                let track = fetch_json(&url).await?;
+                tracks.push(CatalogTrack {
+                    artist: get(&get(&track, "artist")?, "name")?
+                        .as_string()
+                        .unwrap_or_default(),
+                    title: get(&track, "title")?.as_string().unwrap_or_default(),
+                    // deezer reports 0 for unknown tempos
+                    bpm: get(&track, "bpm")?.as_f64().filter(|&bpm| bpm > 0_f64),
+                });
+            }
+            Ok(tracks)
+        })
+    }
+}
+
+fn get(target: &JsValue, key: &str) -> Result<JsValue, LookupError> {
+    Reflect::get(target, &JsValue::from_str(key)).map_err(|_| LookupError::Malformed)
+}
+
+/// Fetches the json at `url`, giving up if it takes longer than `TIMEOUT_MS`
+async fn fetch_json(url: &str) -> Result<JsValue, LookupError> {
+    let init = RequestInit::new();
+    init.set_signal(Some(&AbortSignal::timeout_with_u32(TIMEOUT_MS)));
+    let response = JsFuture::from(window().fetch_with_str_and_init(url, &init))
+        .await
+        .map_err(|_| LookupError::Unreachable)?
+        .unchecked_into::<Response>();
+    if !response.ok() {
+        return Err(LookupError::Unreachable);
+    }
+    let json = response.json().map_err(|_| LookupError::Malformed)?;
+    let response = JsFuture::from(json)
+        .await
+        .map_err(|_| LookupError::Malformed)?;
+    if get(&response, "error")?.is_truthy() {
+        return Err(LookupError::Malformed);
+    }
+    Ok(response)
+}
+
+/// Searches `provider` for a song and compares its published tempo to `bpm`
+#[component]
+pub fn Lookup<P>(provider: P, bpm: Signal<Option<f64>>) -> impl IntoView
+where
+    P: TempoProvider + 'static,
+{
+    let name = provider.name();
+    // a proxy that adds CORS headers, in front of each catalog url
+    let proxy = RwSignal::new(
+        autosave::storage()
+            .and_then(|storage| storage.get_item(PROXY_KEY).ok().flatten())
+            .unwrap_or_default(),
+    );
+    let set_proxy = move |url: String| {
+        let url = url.trim().to_string();
+        if let Some(storage) = autosave::storage() {
+            let _ = storage.set_item(PROXY_KEY, &url);
+        }
+        proxy.set(url);
+    };
+    let search =
+        Action::new_local(move |query: &String| provider.search(query, &proxy.get_untracked()));
+
+    view! {
+        <span class="text-label">"      lookup:  "</span>
+        <input
+            class="bg-field outline-none w-[20ch] text-value"
+            placeholder="song title"
+            on:keydown=move |evt| {
+                if evt.key() == "Enter" {
+                    search.dispatch(event_target_value(&evt));
+                }
+            }
+        />
+        <span class="text-comment">" # "{name}"\n"</span>
+        <span class="text-label">"       proxy:  "</span>
+        <input
+            class="bg-field outline-none w-[20ch] text-value"
+            placeholder="cors proxy url"
+            prop:value=move || proxy.get()
+            on:change=move |evt| set_proxy(event_target_value(&evt))
+        />
+        <span class="text-comment">" # put before each catalog url\n"</span>
+        {move || {
+            let status = |text: String| {
+                view! { <span class="text-comment">{format!("{:>14}{text}\n", "")}</span> }
+                    .into_any()
+            };
+            if search.pending().get() {
+                return status("searching".into());
+            }
+            match search.value().get() {
+                None => ().into_any(),
+                Some(Err(e)) => status(e.to_string()),
+                Some(Ok(tracks)) if tracks.is_empty() => status("no matches".into()),
+                Some(Ok(tracks)) => {
+                    tracks
+                        .into_iter()
+                        .map(|track| {
+                            let catalog = match track.bpm {
+                                Some(catalog) => format!("{catalog:>12.2}: "),
+                                None => format!("{:>12}: ", "???"),
+                            };
+                            let delta = move || match (bpm.get(), track.bpm) {
+                                (Some(measured), Some(catalog)) => {
+                                    format!("{:+6.2} ", measured - catalog)
+                                }
+                                _ => "       ".into(),
+                            };
+                            view! {
+                                <span class="text-label">{catalog}</span>
+                                <span class="text-value">{delta}</span>
+                                <span class="text-comment">
+                                    "# "{track.artist}" - "{track.title}"\n"
+                                </span>
+                            }
+                        })
+                        .collect_view()
+                        .into_any()
+                }
+            }
+        }}
+    }
+}
@@ -1,101 +1,342 @@
 use leptos::ev::{keydown, keyup, KeyboardEvent};
 use leptos::prelude::*;
 use leptos_use::{use_document, use_event_listener};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
 use web_time::{Duration, Instant};
 
+use feedback::Blink;
+use session::{Gap, TapData};
+
+mod allan;
+mod audio;
+mod autosave;
+mod bars;
 mod bpm;
+mod camera;
+mod chat;
+mod click;
+mod crowd;
+mod diagnostics;
+mod edit;
+mod embed;
+mod explain;
+mod export;
+mod feedback;
+mod formula;
+mod fps;
+mod hands;
+mod head;
+mod history;
+mod hue;
+mod import;
+mod latency;
+mod lookup;
+mod midi;
+mod motion;
+mod music;
+mod onset;
+mod palette;
+mod pedal;
+mod poly;
+mod pulse;
+#[cfg(feature = "ransac")]
+mod ransac;
+mod rate;
+mod recorder;
+mod schema;
+mod session;
+mod smf;
+mod soak;
+mod startup;
+mod tempo_map;
+mod theme;
+mod tuplet;
+mod update;
+mod video;
+mod watchdog;
+mod webhook;
+mod zip;
 
 fn main() {
+    startup::mark("wasm-ready");
     console_error_panic_hook::set_once();
     mount_to_body(App);
+    startup::mark("mounted");
 }
 
-#[derive(Default)]
-struct TapData {
-    start: Option<Instant>,
-    timestamps: Vec<u64>,
-}
+#[component]
+fn App() -> impl IntoView {
+    let (reset_sec, set_reset_sec) = signal::<u64>(2);
+    // estimates only see the last this many steps of taps, 0 sees them all
+    let (window_steps, set_window_steps) = signal::<u64>(0);
+    let (tap_data, set_tap_data) = signal::<TapData>(TapData::default());
+    let (left_taps, set_left_taps) = signal::<TapData>(TapData::default());
+    let (poly_mode, set_poly_mode) = signal::<bool>(false);
+    let (alternate_mode, set_alternate_mode) = signal::<bool>(false);
+    let (primary_metric, set_primary_metric) = signal::<Metric>(Metric::ThielSen);
+    let (tuplets_open, set_tuplets_open) = signal::<bool>(false);
+    let (slow_mode, set_slow_mode) = signal::<bool>(false);
+    let (fps_mode, set_fps_mode) = signal::<bool>(false);
+    let (self_test_open, set_self_test_open) = signal::<bool>(false);
+    let (pulse_open, set_pulse_open) = signal::<bool>(false);
+    let (allan_open, set_allan_open) = signal::<bool>(false);
+    let (edit_open, set_edit_open) = signal::<bool>(false);
+    let (formula_open, set_formula_open) = signal::<bool>(false);
+    let (skin_index, set_skin_index) = signal::<u64>(1);
+    let (beats_per_bar, set_beats_per_bar) = signal::<u64>(0);
+    let (double_tap_tenths, set_double_tap_tenths) = signal::<u64>(4);
+    let (chord_steps, set_chord_steps) = signal::<u64>(3);
+    let (min_gap_steps, set_min_gap_steps) = signal::<u64>(MIN_GAP_STEPS);
+    let (max_bpm_steps, set_max_bpm_steps) = signal::<u64>(MAX_BPM_STEPS);
+    // seconds over which a tap's weight halves in the recency-weighted row
+    let (half_life_sec, set_half_life_sec) = signal::<u64>(10);
+    #[cfg(feature = "ransac")]
+    let ransac = ransac::use_ransac();
+    // the last taps are often the least reliable, so a second estimate can leave them out
+    let (settled_taps, set_settled_taps) = signal::<u64>(0);
+    let (count_doubles, set_count_doubles) = signal::<bool>(false);
+    // estimates are recomputed every this many taps, 0 waits for the recompute button
+    let (recompute_every, set_recompute_every) = signal::<u64>(1);
+    let (recomputes, set_recomputes) = signal::<u64>(0);
+    // primary estimate of the session before the current one
+    let (last_bpm, set_last_bpm) = signal::<Option<f64>>(None);
+    let (ctrl_held, set_ctrl_held) = signal::<bool>(false);
+    let (explain_open, set_explain_open) = signal::<bool>(false);
+    let (lookup_open, set_lookup_open) = signal::<bool>(false);
+    let (setup_open, set_setup_open) = signal::<bool>(false);
+    let (palette_open, set_palette_open) = signal::<bool>(false);
+    let (motion_sensitivity, set_motion_sensitivity) = signal::<u64>(0);
+    let (camera_sensitivity, set_camera_sensitivity) = signal::<u64>(0);
+    let (tab_audio_sensitivity, set_tab_audio_sensitivity) = signal::<u64>(0);
 
-impl TapData {
-    /// Appends a new tap datapoint to the end of the vector
-    /// If self.start is None, records `now` as the start and resets timestamps
-    pub fn record(&mut self, now: Instant) {
-        match self.start {
-            Some(start) => self
-                .timestamps
-                .push(now.duration_since(start).as_millis() as u64),
-            None => {
-                self.start = Some(now);
-                self.timestamps = vec![0];
+    // hooks below suspend their background work while the app is idle
+    watchdog::use_idle_watchdog(tap_data);
+    let midi = midi::use_midi_taps();
+    let chat = chat::use_chat_taps();
+    let hands = hands::use_hands();
+    // footswitches show up as a key or a mouse button, each shaped on its own
+    let key_pedal = pedal::use_pedal();
+    let click_pedal = pedal::use_pedal();
+    let recorder = recorder::use_session_recorder();
+    let video = video::use_video_sync();
+    let saved_session = autosave::use_autosave(tap_data);
+    // the taps estimates are made from, leaving out probable double taps unless asked for
+    let offsets = Memo::new(move |_| {
+        let tenths = double_tap_tenths.get();
+        tap_data.with(|taps| {
+            if tenths == 0 || count_doubles.get() {
+                taps.timestamps()
+            } else {
+                bpm::merge_double_taps(&taps.timestamps(), tenths as f64 / 10_f64)
+            }
+        })
+    });
+    // the offsets the estimates were last computed from, with the recompute count they saw
+    let recomputed = Memo::new(move |previous: Option<&(u64, Vec<u64>)>| {
+        let (requested, every) = (recomputes.get(), recompute_every.get() as usize);
+        let offsets = offsets.get();
+        match previous {
+            // only new taps wait, anything else (resets, undo, imports) shows up right away
+            Some((seen, estimated))
+                if *seen == requested
+                    && offsets.starts_with(estimated)
+                    && (every == 0 || offsets.len() % every != 0) =>
+            {
+                (requested, estimated.clone())
             }
+            _ => (requested, offsets),
         }
-    }
-    /// Returns true if the bpm count has been reset
-    pub fn is_reset(&self) -> bool {
-        self.start.is_none() && !self.timestamps.is_empty()
-    }
-    /// Returns true if no tap events have ever been recorded
-    pub fn has_no_taps(&self) -> bool {
-        self.timestamps.is_empty()
-    }
-}
+    });
+    let estimated = Memo::new(move |_| {
+        let window = (window_steps.get() * WINDOW_STEP) as usize;
+        recomputed.with(|(_, offsets)| bpm::windowed(offsets, window).to_vec())
+    });
+    let max_bpm = Signal::derive(move || max_bpm_steps.get() * MAX_BPM_STEP);
+    let primary_bpm = Signal::derive(move || {
+        let estimate = primary_metric.get().estimate(&estimated.read());
+        estimate
+            .and_then(|bpm| bpm::plausible(bpm, max_bpm.get()))
+            .ok()
+    });
 
-#[derive(Debug, Clone)]
-enum BlinkColor {
-    Orange,
-    Violet,
-}
+    let settled_bpm = Signal::derive(move || {
+        let offsets = estimated.read();
+        let settled = &offsets[..offsets.len().saturating_sub(settled_taps.get() as usize)];
+        let estimate = primary_metric.get().estimate(settled);
+        estimate
+            .and_then(|bpm| bpm::plausible(bpm, max_bpm.get()))
+            .ok()
+    });
 
-impl BlinkColor {
-    pub fn tw_class(&self) -> &str {
-        match &self {
-            Self::Orange => "border-orange-400",
-            Self::Violet => "border-violet-400",
-        }
-    }
-}
+    let webhook = webhook::use_webhook(primary_bpm);
+    // one grid for everything that pulses, so they pulse together
+    let clock = pulse::use_beat_clock(tap_data, estimated);
+    let hue = hue::use_hue_lights(tap_data, clock);
 
-#[component]
-fn App() -> impl IntoView {
-    let (reset_sec, set_reset_sec) = signal::<u64>(2);
-    let (border_state, set_border_state) = signal::<Option<BlinkColor>>(None);
-    let (tap_data, set_tap_data) = signal::<TapData>(TapData::default());
-    let (active_timeout, set_active_timeout) = signal::<Option<TimeoutHandle>>(None);
-    let (ctrl_held, set_ctrl_held) = signal::<bool>(false);
+    // exports fall back on the last session, so it can be saved after a reset
+    let export_bpm = Signal::derive(move || primary_bpm.get().or(last_bpm.get()));
+    head::use_page_head(export_bpm, tap_data, clock, slow_mode);
+
+    let throttled = RwSignal::new(false);
+    watchdog::use_throttle_detector(throttled);
+    let feedback = feedback::use_feedback();
+    let latency = latency::use_latency_test(move || feedback.blink(Blink::Tap));
 
-    let blink_border = move |color: BlinkColor| {
-        set_border_state.set(Some(color));
-        set_timeout(
-            move || set_border_state.set(None),
-            Duration::from_millis(50),
-        );
+    // poly mode's left hand keys tap a stream of their own, which ends on its own gaps
+    let right = Stream::new(tap_data, set_tap_data);
+    let left = Stream::new(left_taps, set_left_taps);
+    // whether the last session was closed by the device sleeping rather than a reset
+    let slept = RwSignal::new(false);
+    let end_stream = move |stream: Stream| {
+        stream.clear_timeouts();
+        feedback.clear_warning();
+        slept.set(false);
+        if stream.taps == tap_data {
+            if let Some(bpm) = primary_bpm.get_untracked() {
+                set_last_bpm.set(Some(bpm));
+            }
+        }
+        stream.set_taps.write().run(history::Command::Reset);
+        feedback.blink(Blink::Reset);
+    };
+    let reset_session = move || {
+        end_stream(left);
+        end_stream(right);
     };
 
-    let handle_beat_input = move || {
-        let now = Instant::now();
-        if let Some(handle) = active_timeout.get() {
-            handle.clear();
+    // each mode starts from the minimum gap that suits it
+    Effect::new(move |_| {
+        set_min_gap_steps.set(if slow_mode.get() {
+            SLOW_MIN_GAP_STEPS
+        } else {
+            MIN_GAP_STEPS
+        })
+    });
+    // returns true if the tap was added to the session
+    let record_beat = move |stream: Stream| {
+        if latency.is_running() {
+            latency.record_tap(window().performance().map_or(0_f64, |p| p.now()));
+            return false;
         }
-        let new_timeout = set_timeout_with_handle(
-            move || {
-                set_tap_data.write().start = None;
-                blink_border(BlinkColor::Orange);
-            },
-            Duration::from_secs(reset_sec.get()),
-        )
-        .expect("Set timeout should not fail");
-        set_active_timeout.set(Some(new_timeout));
-        set_tap_data.write().record(now);
-        blink_border(BlinkColor::Violet);
+        // taps land late by the device's latency, so each is timed from when it was made
+        let now = latency.tapped_at(Instant::now());
+        // inputs sooner than this after a tap are switch bounce or a double fire, not beats
+        let min_gap = Duration::from_millis(min_gap_steps.get_untracked() * MIN_GAP_STEP_MS);
+        if stream
+            .taps
+            .with_untracked(|taps| taps.since_last_tap(now))
+            .is_some_and(|gap| gap < min_gap)
+        {
+            return false;
+        }
+        // slow mode counts the reset in minutes instead
+        let reset_after =
+            Duration::from_secs(reset_sec.get() * if slow_mode.get() { 60 } else { 1 });
+        // the reset timeout only shows the reset, it may fire late or not at all in a
+        // throttled or sleeping tab, so the gap since the last tap is what ends a session
+        let wall_ms = js_sys::Date::now();
+        let since_wall_ms = wall_ms - stream.last_wall_ms.get_value();
+        let wall = Duration::from_millis(since_wall_ms.max(0_f64) as u64);
+        stream.last_wall_ms.set_value(wall_ms);
+        match stream
+            .taps
+            .with_untracked(|taps| taps.gap(now, wall, reset_after))
+        {
+            Gap::Beat => {}
+            Gap::Reset => end_stream(stream),
+            Gap::Suspend => {
+                end_stream(stream);
+                slept.set(true);
+            }
+        }
+        if throttled.get_untracked() {
+            throttled.set(false);
+        }
+        stream.clear_timeouts();
+        feedback.clear_warning();
+        let new_timeout = set_timeout_with_handle(move || end_stream(stream), reset_after)
+            .expect("Set timeout should not fail");
+        stream.reset_timeout.set_value(Some(new_timeout));
+        // short resets get their warning halfway through instead
+        let warn_after = reset_after
+            .saturating_sub(feedback::WARNING_LEAD)
+            .max(reset_after / 2);
+        let new_timeout = set_timeout_with_handle(move || feedback.warn(), warn_after)
+            .expect("Set timeout should not fail");
+        stream.warning_timeout.set_value(Some(new_timeout));
+        stream.set_taps.write().run(history::Command::Tap(now));
+        midi.on_tap(primary_bpm.get_untracked());
+        // the recordings run on clocks of their own, so they take the latency off too
+        let latency_ms = latency.calibration();
+        recorder.on_tap(window().performance().map_or(0_f64, |p| p.now()) - latency_ms);
+        video.on_tap(latency_ms);
+        feedback.blink(Blink::Tap);
+        true
+    };
+    let handle_beat_input = move || {
+        record_beat(right);
+    };
+    let min_gap_ms = Signal::derive(move || min_gap_steps.get() * MIN_GAP_STEP_MS);
+    let soak = soak::use_soak_test(tap_data, min_gap_ms, handle_beat_input);
+
+    // everything the command palette can do, built each time it opens
+    let palette_commands = move || {
+        let toggle = |set: WriteSignal<bool>| move || set.update(|open| *open = !*open);
+        let mut commands = vec![
+            palette::command("reset session", reset_session),
+            palette::command("undo", move || {
+                set_tap_data.write().undo();
+            }),
+            palette::command("redo", move || {
+                set_tap_data.write().redo();
+            }),
+            palette::command("join previous", move || {
+                set_tap_data.write().run(history::Command::Join)
+            }),
+            palette::command("recompute estimates", move || *set_recomputes.write() += 1),
+            palette::command("toggle setup", toggle(set_setup_open)),
+            palette::command("toggle explain", toggle(set_explain_open)),
+            palette::command("toggle lookup", toggle(set_lookup_open)),
+            palette::command("toggle poly", toggle(set_poly_mode)),
+            palette::command("toggle alternate", toggle(set_alternate_mode)),
+            palette::command("toggle formula", toggle(set_formula_open)),
+            palette::command("toggle allan", toggle(set_allan_open)),
+            palette::command("toggle edit", toggle(set_edit_open)),
+            palette::command("toggle tuplets", toggle(set_tuplets_open)),
+            palette::command("toggle slow", toggle(set_slow_mode)),
+            palette::command("toggle fps", toggle(set_fps_mode)),
+            palette::command("toggle self-test", toggle(set_self_test_open)),
+            palette::command("toggle pulse", toggle(set_pulse_open)),
+            palette::command("toggle counting double taps", toggle(set_count_doubles)),
+            palette::command("next skin", move || {
+                set_skin_index.update(|index| *index = *index % theme::Skin::ALL.len() as u64 + 1)
+            }),
+            palette::command("start/stop latency test", move || latency.toggle()),
+        ];
+        commands.extend(Metric::ALL.iter().map(|&metric| {
+            palette::command(format!("make {} primary", metric.label()), move || {
+                set_primary_metric.set(metric)
+            })
+        }));
+        commands
     };
 
+    motion::use_motion_beats(motion_sensitivity, handle_beat_input);
+    camera::use_camera_beats(camera_sensitivity, handle_beat_input);
+    audio::use_display_audio_beats(tab_audio_sensitivity, handle_beat_input);
+
     let _cleanup = use_event_listener(use_document(), keyup, move |evt: KeyboardEvent| {
         // Ctrl is released
         if evt.key_code() == 17 {
             set_ctrl_held.set(false);
         }
+        key_pedal.release(evt.time_stamp());
     });
 
+    // event time of the last key counted as a beat, right hand then left
+    let last_key_beat = StoredValue::new([f64::NEG_INFINITY; 2]);
     let _cleanup = use_event_listener(use_document(), keydown, move |evt: KeyboardEvent| {
         let disabled_keys = [
             0,  // Unidentified
@@ -108,102 +349,756 @@ fn App() -> impl IntoView {
             91, // Meta
             92, // Meta
         ];
+        if is_typing(&evt) {
+            return;
+        }
         // Ctrl is pressed
         if evt.key_code() == 17 {
             set_ctrl_held.set(true);
-        } else if !disabled_keys.contains(&evt.key_code()) && !ctrl_held.get() {
-            handle_beat_input();
+        } else if ctrl_held.get() {
+            // Ctrl+Z undoes, Ctrl+Shift+Z or Ctrl+Y redoes, Ctrl+K opens the command palette,
+            // Ctrl+1-9 sets the reset
+            let code = evt.code();
+            let preset = code
+                .strip_prefix("Digit")
+                .and_then(|d| d.parse::<u64>().ok());
+            match (code.as_str(), evt.shift_key()) {
+                ("KeyZ", false) => {
+                    evt.prevent_default();
+                    set_tap_data.write().undo();
+                }
+                ("KeyZ", true) | ("KeyY", _) => {
+                    evt.prevent_default();
+                    set_tap_data.write().redo();
+                }
+                ("KeyK", _) => {
+                    evt.prevent_default();
+                    set_palette_open.set(true);
+                }
+                _ => {
+                    if let Some(secs @ 1..=9) = preset {
+                        evt.prevent_default();
+                        set_reset_sec.set(secs);
+                    }
+                }
+            }
+        } else if !disabled_keys.contains(&evt.key_code()) {
+            if !key_pedal.press(evt.time_stamp(), evt.repeat()) {
+                return;
+            }
+            let hand = poly::is_left_hand(&evt.code());
+            let split = poly_mode.get() || alternate_mode.get();
+            // keys pressed together are one beat, as two fingers drumming roll over
+            let time = evt.time_stamp();
+            let chord_ms = (chord_steps.get() * CHORD_STEP_MS) as f64;
+            let chord = (split && hand) as usize;
+            if time - last_key_beat.get_value()[chord] < chord_ms {
+                return;
+            }
+            last_key_beat.update_value(|times| times[chord] = time);
+            if poly_mode.get() && hand {
+                record_beat(left);
+            } else if record_beat(right) && alternate_mode.get() {
+                // a tap the min gap or latency test took isn't in the session to mark
+                hands.record(hand, tap_data.with_untracked(TapData::len));
+            }
         }
     });
+    startup::mark("input-ready");
 
     view! {
-        <div class="flex flex-col h-screen" on:mousedown=move |_| handle_beat_input()>
-            <div class="flex justify-center items-center w-full h-full min-h-screen select-none bg-zinc-800">
+        <div
+            class="flex flex-col h-screen"
+            on:mousedown=move |evt| {
+                if click_pedal.press(evt.time_stamp(), false) {
+                    handle_beat_input();
+                }
+            }
+            on:mouseup=move |evt| click_pedal.release(evt.time_stamp())
+        >
+            <div class=move || {
+                format!(
+                    "flex justify-center items-center w-full h-full min-h-screen select-none {} {}",
+                    theme::skin(skin_index.get()).class(),
+                    feedback.background_class(),
+                )
+            }>
+                <Show when=move || pulse_open.get()>
+                    <pulse::BeatPulse clock offsets=estimated />
+                </Show>
                 <pre
-                    // set border color according to border_state
+                    // set border and background colors according to the flash showing
                     class=move || {
                         let pre_class = concat!(
-                            "font-mono bg-zinc-800 text-white select-text",
+                            "relative font-mono text-ink select-text",
                             "   border-[0.5vw]    px-[3.2vw]    py-[2.5vw]    text-[3.0vw] ",
                             "xl:border-[0.3vw] xl:px-[1.7vw] xl:py-[1.3vw] xl:text-[1.6vw] ",
                         );
-                        match border_state.get() {
-                            Some(blink_color) => format!("{pre_class} {}", blink_color.tw_class()),
-                            None => {
-                                format!("{pre_class} border-white transition-colors duration-400")
-                            }
-                        }
+                        format!(
+                            "{pre_class} {} {}",
+                            feedback.border_class(),
+                            feedback.background_class(),
+                        )
                     }
                     // prevent clicks in the ui from triggering a beat update
                     on:mousedown=move |e| e.stop_propagation()
                 >
                     <span>
                         "lucdar's bpm counter" <Show when=move || tap_data.read().has_no_taps()>
-                            <span class="text-zinc-400">" - tap/type/click to begin!"</span>
+                            <span class="text-comment">" - tap/type/click to begin!"</span>
                         </Show> "\n\n"
                     </span>
-                    <ResetControl reset_sec set_reset_sec />
-                    <BpmTable tap_data />
-                    <Footer tap_data />
+                    <Show when=move || palette_open.get()>
+                        <palette::CommandPalette
+                            set_open=set_palette_open
+                            commands=palette_commands()
+                        />
+                    </Show>
+                    <update::UpdateOffer />
+                    {saved_session
+                        .map(|saved| view! { <autosave::RestoreOffer saved set_tap_data /> })}
+                    <Show
+                        when=move || slow_mode.get()
+                        fallback=move || {
+                            view! {
+                                <Stepper
+                                    label="reset-sec"
+                                    value=reset_sec
+                                    set_value=set_reset_sec
+                                    min=1
+                                    max=9
+                                    description="secs before bpm is reset"
+                                />
+                            }
+                        }
+                    >
+                        <Stepper
+                            label="reset-min"
+                            value=reset_sec
+                            set_value=set_reset_sec
+                            min=1
+                            max=9
+                            description="mins before count is reset"
+                        />
+                    </Show>
+                    <Stepper
+                        label="window"
+                        value=window_steps
+                        set_value=set_window_steps
+                        min=0
+                        max=16
+                        description=Signal::derive(move || match window_steps.get() * WINDOW_STEP {
+                            0 => "estimates use every tap".to_string(),
+                            taps => format!("estimates use the last {taps} taps"),
+                        })
+                    />
+                    <Show when=move || setup_open.get()>
+                        <Stepper
+                            label="motion"
+                            value=motion_sensitivity
+                            set_value=set_motion_sensitivity
+                            min=0
+                            max=9
+                            description="tap phone to beat, 0 is off"
+                            on_step=motion::on_step
+                        />
+                        <Stepper
+                            label="camera"
+                            value=camera_sensitivity
+                            set_value=set_camera_sensitivity
+                            min=0
+                            max=9
+                            description="watch an led blink, 0 is off"
+                        />
+                        <Stepper
+                            label="tab-audio"
+                            value=tab_audio_sensitivity
+                            set_value=set_tab_audio_sensitivity
+                            min=0
+                            max=9
+                            description="hear another tab, 0 is off"
+                        />
+                        <midi::MidiControls midi />
+                        <chat::ChatControls chat />
+                        <webhook::WebhookControls webhook />
+                        <hue::HueControls hue />
+                        <latency::LatencyControls test=latency />
+                        <recorder::RecorderControls recorder />
+                        <video::VideoControls video />
+                        <theme::SkinControls skin_index set_skin_index />
+                        <feedback::FeedbackControls feedback />
+                        <import::Import set_tap_data />
+                        <autosave::StoredData reset=reset_session set_tap_data />
+                        <click::ClickExport bpm=export_bpm />
+                        <tempo_map::TempoMapExport offsets=estimated bpm=export_bpm />
+                        <Show when=soak::is_enabled>
+                            <soak::SoakControls soak tap_data />
+                            <startup::StartupTimes />
+                        </Show>
+                        <Switch
+                            label="poly"
+                            value=poly_mode
+                            set_value=set_poly_mode
+                            description="left/right hand keys split"
+                        />
+                        <Switch
+                            label="alternate"
+                            value=alternate_mode
+                            set_value=set_alternate_mode
+                            description="left/right hand timing, one tempo"
+                        />
+                        <Switch
+                            label="formula"
+                            value=formula_open
+                            set_value=set_formula_open
+                            description="type in a metric of your own"
+                        />
+                        <Switch
+                            label="allan"
+                            value=allan_open
+                            set_value=set_allan_open
+                            description="timing error vs window size"
+                        />
+                        <Switch
+                            label="edit"
+                            value=edit_open
+                            set_value=set_edit_open
+                            description="nudge, delete or split at taps"
+                        />
+                        <Switch
+                            label="tuplets"
+                            value=tuplets_open
+                            set_value=set_tuplets_open
+                            description="related tempos to modulate to"
+                        />
+                        <Switch
+                            label="slow"
+                            value=slow_mode
+                            set_value=set_slow_mode
+                            description="breaths, reps, machine cycles"
+                        />
+                        <Switch
+                            label="fps"
+                            value=fps_mode
+                            set_value=set_fps_mode
+                            description="measure the display refresh rate"
+                        />
+                        <Switch
+                            label="self-test"
+                            value=self_test_open
+                            set_value=set_self_test_open
+                            description="how far to trust this device"
+                        />
+                        <Switch
+                            label="pulse"
+                            value=pulse_open
+                            set_value=set_pulse_open
+                            description="the background glows on the beat"
+                        />
+                        <Stepper
+                            label="double-tap"
+                            value=double_tap_tenths
+                            set_value=set_double_tap_tenths
+                            min=0
+                            max=9
+                            description=Signal::derive(move || match double_tap_tenths.get() {
+                                0 => "count every tap".to_string(),
+                                tenths => format!("merge gaps under {tenths}0%"),
+                            })
+                        />
+                        <Stepper
+                            label="chord"
+                            value=chord_steps
+                            set_value=set_chord_steps
+                            min=0
+                            max=9
+                            description=Signal::derive(move || {
+                                match chord_steps.get() * CHORD_STEP_MS {
+                                    0 => "every key is a tap".to_string(),
+                                    ms => format!("keys within {ms}ms are one tap"),
+                                }
+                            })
+                        />
+                        <Stepper
+                            label="min-gap"
+                            value=min_gap_steps
+                            set_value=set_min_gap_steps
+                            min=0
+                            max=50
+                            description=Signal::derive(move || {
+                                match min_gap_steps.get() * MIN_GAP_STEP_MS {
+                                    0 => "taps can come at any pace".to_string(),
+                                    ms => format!("ignore taps within {ms}ms"),
+                                }
+                            })
+                        />
+                        <Stepper
+                            label="max-bpm"
+                            value=max_bpm_steps
+                            set_value=set_max_bpm_steps
+                            min=0
+                            max=20
+                            description=Signal::derive(move || match max_bpm.get() {
+                                0 => "any estimate is shown".to_string(),
+                                max => format!("flag estimates over {max}"),
+                            })
+                        />
+                        <Stepper
+                            label="half-life"
+                            value=half_life_sec
+                            set_value=set_half_life_sec
+                            min=1
+                            max=60
+                            description=Signal::derive(move || {
+                                format!("recent taps weigh most over {}s", half_life_sec.get())
+                            })
+                        />
+                        {
+                            #[cfg(feature = "ransac")]
+                            let controls = view! { <ransac::RansacControls ransac /> };
+                            #[cfg(not(feature = "ransac"))]
+                            let controls = ();
+                            controls
+                        }
+                        <Stepper
+                            label="settled"
+                            value=settled_taps
+                            set_value=set_settled_taps
+                            min=0
+                            max=2
+                            description=Signal::derive(move || match settled_taps.get() {
+                                0 => "no settled estimate".to_string(),
+                                1 => "another leaving out the last tap".to_string(),
+                                taps => format!("another leaving out the last {taps} taps"),
+                            })
+                        />
+                        <pedal::PedalControls label="key-rearm" pedal=key_pedal />
+                        <pedal::PedalControls label="click-rearm" pedal=click_pedal />
+                        <Stepper
+                            label="recompute"
+                            value=recompute_every
+                            set_value=set_recompute_every
+                            min=0
+                            max=9
+                            description=Signal::derive(move || match recompute_every.get() {
+                                0 => "estimates wait for a click".to_string(),
+                                1 => "estimates update every tap".to_string(),
+                                every => format!("estimates every {every} taps"),
+                            })
+                        />
+                        <Stepper
+                            label="bar-beats"
+                            value=beats_per_bar
+                            set_value=set_beats_per_bar
+                            min=0
+                            max=16
+                            description="beats per bar, 0 hides count"
+                        />
+                    </Show>
+                    "\n"
+                    <BpmTable
+                        offsets=estimated
+                        primary=primary_metric
+                        set_primary=set_primary_metric
+                        slow=slow_mode
+                        max_bpm
+                        recorded=Signal::derive(move || tap_data.with(TapData::len))
+                        half_life=half_life_sec
+                    />
+                    {
+                        #[cfg(feature = "ransac")]
+                        let metric = view! {
+                            <ransac::RansacMetric offsets=estimated ransac slow=slow_mode max_bpm />
+                        };
+                        #[cfg(not(feature = "ransac"))]
+                        let metric = ();
+                        metric
+                    }
+                    <embed::CustomMetrics offsets=estimated slow=slow_mode />
+                    <Show when=move || formula_open.get()>
+                        <formula::FormulaMetric offsets=estimated slow=slow_mode />
+                    </Show>
+                    <Show when=move || settled_taps.get() != 0>
+                        <span class="text-label">{format!("{:>12}: ", "settled")}</span>
+                        <span class="text-value">
+                            {move || rate::format_rate(settled_bpm.get(), slow_mode.get())}
+                        </span>
+                        <span class="text-comment">
+                            {move || {
+                                let metric = primary_metric.get().label();
+                                format!("# {metric} without the last {}\n", settled_taps.get())
+                            }}
+                        </span>
+                    </Show>
+                    <Show when=move || recomputed.read().1.len() != offsets.read().len()>
+                        <span class="text-label">{format!("{:>12}: ", "stale")}</span>
+                        <span class="text-value">
+                            {move || {
+                                let estimated = recomputed.read().1.len();
+                                let new = offsets.read().len().abs_diff(estimated);
+                                format!("{new:6} ")
+                            }}
+                        </span>
+                        <span class="text-comment">"# new taps, "</span>
+                        <button
+                            class="text-comment hover:text-value"
+                            on:mousedown=move |_| *set_recomputes.write() += 1
+                        >
+                            "recompute"
+                        </button>
+                        "\n"
+                    </Show>
+                    // the last session stays visible until this one has an estimate of its own
+                    <Show when=move || primary_bpm.get().is_none() && last_bpm.get().is_some()>
+                        <span class="text-dim">
+                            {format!("{:>12}: ", "last")}
+                            {move || rate::format_rate(last_bpm.get(), slow_mode.get())}
+                            {move || {
+                                if slept.get() {
+                                    "# closed when the device slept\n"
+                                } else {
+                                    "# the previous session\n"
+                                }
+                            }}
+                        </span>
+                    </Show>
+                    <Show when=move || throttled.get()>
+                        <span class="text-label">{format!("{:>12}: ", "throttled")}</span>
+                        <span class="text-comment">
+                            "       # timers ran late, resets may show late\n"
+                        </span>
+                    </Show>
+                    <Show when=move || beats_per_bar.get() != 0>
+                        <bars::BarCounter tap_data beats_per_bar />
+                    </Show>
+                    <DoubleTaps
+                        tap_data
+                        tenths=double_tap_tenths
+                        counted=count_doubles
+                        set_counted=set_count_doubles
+                    />
+                    <Show when=move || fps_mode.get()>
+                        <fps::FrameRate />
+                    </Show>
+                    <Show when=move || self_test_open.get()>
+                        <diagnostics::SelfTest />
+                    </Show>
+                    <Show when=move || allan_open.get()>
+                        <allan::AllanTable tap_data />
+                    </Show>
+                    <Show when=move || edit_open.get()>
+                        <edit::TapEditor tap_data set_tap_data slow=slow_mode />
+                    </Show>
+                    <Show when=move || tuplets_open.get()>
+                        <tuplet::TupletTable bpm=primary_bpm />
+                    </Show>
+                    <Show when=move || poly_mode.get()>
+                        <poly::PolyTable left=left_taps right=tap_data />
+                    </Show>
+                    <Show when=move || alternate_mode.get()>
+                        <hands::HandsTable hands tap_data />
+                    </Show>
+                    <chat::CrowdTempo chat slow=slow_mode />
+                    <video::VideoPane video slow=slow_mode />
+                    <Show when=move || explain_open.get()>
+                        <Explanation offsets=estimated />
+                    </Show>
+                    <Show when=move || lookup_open.get()>
+                        "\n"
+                        <lookup::Lookup
+                            provider=lookup::Deezer
+                            bpm=primary_bpm
+                        />
+                    </Show>
+                    <Footer
+                        tap_data
+                        explain_open
+                        set_explain_open
+                        lookup_open
+                        set_lookup_open
+                        setup_open
+                        set_setup_open
+                    />
                 </pre>
             </div>
         </div>
     }
 }
 
+/// A labelled setting that can be stepped up and down within `min..=max`
 #[component]
-fn ResetControl(reset_sec: ReadSignal<u64>, set_reset_sec: WriteSignal<u64>) -> impl IntoView {
+fn Stepper(
+    label: &'static str,
+    value: ReadSignal<u64>,
+    set_value: WriteSignal<u64>,
+    min: u64,
+    max: u64,
+    #[prop(into)] description: Signal<String>,
+    /// Runs with the new value inside the click that stepped it, for browser apis that
+    /// need a user gesture
+    #[prop(optional)]
+    on_step: Option<fn(u64)>,
+) -> impl IntoView {
+    let step = move |stepped: u64| {
+        set_value.set(stepped);
+        if let Some(on_step) = on_step {
+            on_step(stepped);
+        }
+    };
+
     view! {
-        <span class="text-green-400">"   reset-sec:  "</span>
+        <span class="text-label">{format!("{label:>12}:  ")}</span>
         <button
-            class="hover:text-violet-400"
+            class="hover:text-value"
             on:mousedown=move |_| {
-                if reset_sec.get() < 9 {
-                    *set_reset_sec.write() += 1;
+                if value.get() < max {
+                    step(value.get() + 1);
                 }
             }
         >
             "↑"
         </button>
-        <span class="text-violet-400">" "{move || reset_sec.get()}" "</span>
+        <span class="text-value">" "{move || value.get()}" "</span>
         <button
-            class="hover:text-violet-400"
+            class="hover:text-value"
             on:mousedown=move |_| {
-                if reset_sec.get() > 1 {
-                    *set_reset_sec.write() -= 1;
+                if value.get() > min {
+                    step(value.get() - 1);
                 }
             }
         >
             "↓"
         </button>
-        <span class="text-zinc-400">" # secs before bpm is reset\n\n"</span>
+        <span class="text-comment">" # "{move || description.get()}"\n"</span>
+    }
+}
+
+/// The estimators shown in the table, one of which is primary and feeds the other features
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Metric {
+    Direct,
+    LinReg,
+    L1Reg,
+    Robust,
+    ThielSen,
+    #[cfg(feature = "tracker")]
+    Tracker,
+    #[cfg(feature = "repeated-median")]
+    RepMedian,
+    #[cfg(feature = "autocorrelation")]
+    Autocorr,
+}
+
+impl Metric {
+    /// Every metric in this build, some of which are behind cargo features
+    pub const ALL: &[Metric] = &[
+        Self::Direct,
+        Self::LinReg,
+        Self::L1Reg,
+        Self::Robust,
+        Self::ThielSen,
+        #[cfg(feature = "tracker")]
+        Self::Tracker,
+        #[cfg(feature = "repeated-median")]
+        Self::RepMedian,
+        #[cfg(feature = "autocorrelation")]
+        Self::Autocorr,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match &self {
+            Self::Direct => "direct",
+            Self::LinReg => "lin-reg",
+            Self::L1Reg => "l1-reg",
+            Self::Robust => "robust",
+            Self::ThielSen => "thiel-sen",
+            #[cfg(feature = "tracker")]
+            Self::Tracker => "tracker",
+            #[cfg(feature = "repeated-median")]
+            Self::RepMedian => "rep-median",
+            #[cfg(feature = "autocorrelation")]
+            Self::Autocorr => "autocorr",
+        }
+    }
+
+    pub fn estimator(&self) -> fn(&[u64]) -> Result<f64, bpm::BpmCalculationError> {
+        match &self {
+            Self::Direct => bpm::direct_count,
+            Self::LinReg => bpm::simple_regression,
+            Self::L1Reg => bpm::least_absolute,
+            Self::Robust => bpm::robust_regression,
+            Self::ThielSen => bpm::thiel_sen,
+            #[cfg(feature = "tracker")]
+            Self::Tracker => bpm::alpha_beta,
+            #[cfg(feature = "repeated-median")]
+            Self::RepMedian => bpm::repeated_median,
+            #[cfg(feature = "autocorrelation")]
+            Self::Autocorr => bpm::autocorrelation,
+        }
+    }
+
+    /// One line on what the metric does, shown beside it in the table
+    pub fn description(&self) -> &'static str {
+        match &self {
+            Self::Direct => "n - 1 divided by delta t",
+            Self::LinReg => "simple linear regression",
+            Self::L1Reg => "least absolute deviations",
+            Self::Robust => "off-grid taps weigh less",
+            Self::ThielSen => "the \"median\" of the bpms",
+            #[cfg(feature = "tracker")]
+            Self::Tracker => "alpha-beta, follows drift",
+            #[cfg(feature = "repeated-median")]
+            Self::RepMedian => "median of per-tap medians",
+            #[cfg(feature = "autocorrelation")]
+            Self::Autocorr => "comb filter, skips gaps",
+        }
+    }
+
+    /// The metric's formula and when it can be trusted, shown when the description is hovered
+    pub fn details(&self) -> [&'static str; 2] {
+        match &self {
+            Self::Direct => [
+                "(n - 1) * 60000 / (last - first)",
+                "only the first and last taps count",
+            ],
+            Self::LinReg => [
+                "60000 * cov(ms, beat) / var(ms)",
+                "assumes small, even timing errors",
+            ],
+            Self::L1Reg => [
+                "lin-reg, reweighted by 1 / |r| until settled",
+                "r is ms off its own line, bad taps pull less",
+            ],
+            Self::Robust => [
+                "lin-reg, weight 1 / (1 + (r/2.385s)^2)",
+                "r is ms off the thiel-sen grid",
+            ],
+            Self::ThielSen => [
+                "60000 * median of beats/ms per pair",
+                "shrugs off up to 29% bad pairs",
+            ],
+            #[cfg(feature = "tracker")]
+            Self::Tracker => [
+                "period += 0.1 * (tap - predicted)",
+                "settles in a few taps, hates gaps",
+            ],
+            #[cfg(feature = "repeated-median")]
+            Self::RepMedian => [
+                "60000 * median over taps of median slope",
+                "shrugs off up to 50% bad taps",
+            ],
+            #[cfg(feature = "autocorrelation")]
+            Self::Autocorr => [
+                "period tap pairs hit most at 1-4x",
+                "40 to 300 bpm, through missed beats",
+            ],
+        }
+    }
+
+    /// Where the metric is explained at more length
+    pub fn link(&self) -> &'static str {
+        match &self {
+            Self::LinReg => "https://seismo.berkeley.edu/~kirchner/eps_120/Toolkits/Toolkit_10.pdf",
+            Self::ThielSen => "https://en.wikipedia.org/wiki/Theil%E2%80%93Sen_estimator",
+            _ => "https://laclark.me/blog/bpm/",
+        }
+    }
+
+    /// How many of `offsets` the estimate effectively rests on
+    pub fn taps_used(&self, offsets: &[u64]) -> usize {
+        match &self {
+            Self::Robust => bpm::robust_weights(offsets).map_or(offsets.len(), |weights| {
+                bpm::effective_taps(&weights).round() as usize
+            }),
+            _ => offsets.len(),
+        }
+    }
+
+    /// The metric's estimate for `offsets`, which is always a finite number
+    pub fn estimate(&self, offsets: &[u64]) -> Result<f64, bpm::BpmCalculationError> {
+        (self.estimator())(offsets).and_then(bpm::finite)
     }
 }
 
 #[component]
-fn BpmTable(tap_data: ReadSignal<TapData>) -> impl IntoView {
+fn BpmTable(
+    offsets: Memo<Vec<u64>>,
+    primary: ReadSignal<Metric>,
+    set_primary: WriteSignal<Metric>,
+    slow: ReadSignal<bool>,
+    /// Estimates over this are flagged rather than shown, 0 shows them all
+    max_bpm: Signal<u64>,
+    /// Taps in the session, which merging, windowing and recompute waits leave out of `offsets`
+    recorded: Signal<usize>,
+    /// Seconds over which a tap's weight halves in the recency-weighted row
+    half_life: ReadSignal<u64>,
+) -> impl IntoView {
+    // clicking a metric's label makes it the primary one, marked with a star
+    let metric_label = move |metric: Metric| {
+        view! {
+            <button class="hover:text-value" on:mousedown=move |_| set_primary.set(metric)>
+                {move || {
+                    let marker = if primary.get() == metric { "*" } else { "" };
+                    format!("{:>12}: ", format!("{marker}{}", metric.label()))
+                }}
+            </button>
+        }
+    };
+
+    // rows note when fewer taps went into them than were recorded
+    let describe = move |metric: Metric| {
+        let (used, recorded) = (offsets.with(|o| metric.taps_used(o)), recorded.get());
+        if used < recorded {
+            format!("{} ({used}/{recorded})", metric.description())
+        } else {
+            metric.description().to_string()
+        }
+    };
+
     // creates a row with formatted calculations
     macro_rules! render_bpm_metric {
-        ($label:expr, $algorithm:expr, $description:expr) => {
+        ($metric:expr) => {
+            render_bpm_metric!(
+                metric_label($metric),
+                move |offsets: &[u64]| {
+                    let estimate = $metric.estimate(offsets);
+                    estimate.and_then(|bpm| bpm::plausible(bpm, max_bpm.get()))
+                },
+                |result: Option<f64>| rate::format_rate(result, slow.get()),
+                describe($metric),
+                $metric.details().to_vec(),
+                Some($metric.link())
+            )
+        };
+        (
+            $label:expr,
+            $algorithm:expr,
+            $format:expr,
+            $description:expr,
+            $details:expr,
+            $link:expr
+        ) => {{
+            let result = Memo::new(move |_| $algorithm(&offsets.read()));
             view! {
                 // align and color the label
-                <span class="text-green-400">
-                    {format!("{:>12}: ", $label)}
-                </span>
-                <span class="text-violet-400">
-                    {move || {
-                        match $algorithm(&tap_data.read().timestamps)
-                            // .inspect_err(|e| log!("{e:?}"))
-                            .ok()
-                        {
-                            Some(bpm) => format!("{bpm:6.2} "),
-                            None => "000.00 ".into(),
-                        }
-                    }}
+                <span class="text-label">{$label}</span>
+                <span class="text-value">{move || ($format)(result.get().ok())}</span>
+                // a missing estimate is expected before the second tap, anything else is noted
+                <span class="text-comment">
+                    "# "
+                    <Tooltip lines=$details link=$link>
+                        {move || match result.get() {
+                            Err(e) if e != bpm::BpmCalculationError::InsufficientData => {
+                                e.to_string()
+                            }
+                            _ => $description.to_string(),
+                        }}
+                    </Tooltip>
+                    "\n"
                 </span>
-                <span class="text-zinc-400">"# "{$description}"\n"</span>
             }
-        };
+        }};
     }
 
     fn slice_len(ts: &[u64]) -> Result<u64, bpm::BpmCalculationError> {
@@ -211,26 +1106,261 @@ fn BpmTable(tap_data: ReadSignal<TapData>) -> impl IntoView {
     }
 
     view! {
-        {render_bpm_metric!("n", slice_len, "the total count of beats")}
-        {render_bpm_metric!("direct", bpm::direct_count, "n - 1 divided by delta t")}
-        {render_bpm_metric!("lin-reg", bpm::simple_regression, "simple linear regression")}
-        {render_bpm_metric!("thiel-sen", bpm::thiel_sen, "the \"median\" of the bpms")}
+        {render_bpm_metric!(
+            format!("{:>12}: ", "n"),
+            slice_len,
+            |n: Option<u64>| format!("{:6} ", n.unwrap_or(0)),
+            "the total count of beats",
+            vec![],
+            None
+        )}
+        {Metric::ALL.iter().map(|&metric| render_bpm_metric!(metric)).collect_view()}
+        {render_bpm_metric!(
+            format!("{:>12}: ", "recent"),
+            move |offsets: &[u64]| {
+                let half_life_ms = half_life.get() as f64 * 1_000_f64;
+                bpm::recency_regression(offsets, half_life_ms)
+                    .and_then(bpm::finite)
+                    .and_then(|bpm| bpm::plausible(bpm, max_bpm.get()))
+            },
+            |result: Option<f64>| rate::format_rate(result, slow.get()),
+            format!("lin-reg, weight halves every {}s", half_life.get()),
+            vec!["lin-reg, weight 0.5^(age / half-life)", "age is ms before the last tap"],
+            None
+        )}
+    }
+}
+
+/// Notes how many probable double taps are left out, with a toggle to count them again
+#[component]
+fn DoubleTaps(
+    tap_data: ReadSignal<TapData>,
+    tenths: ReadSignal<u64>,
+    counted: ReadSignal<bool>,
+    set_counted: WriteSignal<bool>,
+) -> impl IntoView {
+    let doubles = move || {
+        let tenths = tenths.get();
+        tap_data.with(|taps| match tenths {
+            0 => 0,
+            _ => {
+                let merged = bpm::merge_double_taps(&taps.timestamps(), tenths as f64 / 10_f64);
+                taps.len() - merged.len()
+            }
+        })
+    };
+
+    view! {
+        <Show when=move || doubles() != 0>
+            <span class="text-label">{format!("{:>12}: ", "doubles")}</span>
+            <span class="text-value">{move || format!("{:6} ", doubles())}</span>
+            <span class="text-comment">"# "</span>
+            <button
+                class="text-comment hover:text-value"
+                on:mousedown=move |_| set_counted.update(|counted| *counted = !*counted)
+            >
+                {move || if counted.get() { "counted, ignore them" } else { "ignored, count them" }}
+            </button>
+            "\n"
+        </Show>
+    }
+}
+
+#[component]
+fn Explanation(offsets: Memo<Vec<u64>>) -> impl IntoView {
+    view! {
+        "\n"
+        {move || match explain::explain(&offsets.read()) {
+            Ok(notes) => {
+                notes
+                    .into_iter()
+                    .map(|note| {
+                        view! {
+                            <span class="text-label">{format!("{:>12}: ", note.label)}</span>
+                            <span class="text-comment">{note.text}"\n"</span>
+                        }
+                    })
+                    .collect_view()
+                    .into_any()
+            }
+            Err(e) => {
+                view! { <span class="text-comment">{format!("{:>12}  {e}\n", "")}</span> }
+                    .into_any()
+            }
+        }}
+    }
+}
+
+/// A labelled setting that is either on or off
+#[component]
+fn Switch(
+    label: &'static str,
+    value: ReadSignal<bool>,
+    set_value: WriteSignal<bool>,
+    description: &'static str,
+) -> impl IntoView {
+    view! {
+        <span class="text-label">{format!("{label:>12}:  ")}</span>
+        <button
+            class="hover:text-value"
+            on:mousedown=move |_| set_value.update(|value| *value = !*value)
+        >
+            {move || if value.get() { " on" } else { "off" }}
+        </button>
+        <span class="text-comment">"   # "{description}"\n"</span>
+    }
+}
+
+const LINK_CLASS: &str = "hover:text-value transition-colors duration-150";
+
+const POPOVER_CLASS: &str =
+    "absolute left-0 top-full z-10 px-[1ch] whitespace-pre bg-field border border-ink";
+/// How long a touch is held before it opens a tooltip
+const LONG_PRESS: Duration = Duration::from_millis(500);
+/// The estimate window is set in steps of this many taps
+const WINDOW_STEP: u64 = 4;
+/// The chord window is set in steps of this many milliseconds
+const CHORD_STEP_MS: u64 = 10;
+/// The minimum gap is set in steps of this many milliseconds
+const MIN_GAP_STEP_MS: u64 = 10;
+/// Minimum gaps, in steps, that suit each mode: fast drumming, and slow counts like heart
+/// beats and breaths
+const MIN_GAP_STEPS: u64 = 12;
+const SLOW_MIN_GAP_STEPS: u64 = 25;
+/// The plausible bound is set in steps of this many bpm, 400 by default
+const MAX_BPM_STEP: u64 = 50;
+const MAX_BPM_STEPS: u64 = 8;
+
+/// Text that shows `lines` and a link in a popover while hovered, or after a long press
+#[component]
+fn Tooltip(
+    lines: Vec<&'static str>,
+    link: Option<&'static str>,
+    children: Children,
+) -> impl IntoView {
+    let (open, set_open) = signal(false);
+    let press = StoredValue::new(None::<TimeoutHandle>);
+    let has_tip = !lines.is_empty();
+    let tip = lines.join("\n");
+
+    view! {
+        <span
+            class="relative"
+            on:mouseenter=move |_| set_open.set(has_tip)
+            on:mouseleave=move |_| set_open.set(false)
+            on:touchstart=move |_| {
+                let toggle = move || set_open.update(|open| *open = !*open && has_tip);
+                press.set_value(set_timeout_with_handle(toggle, LONG_PRESS).ok());
+            }
+            // lifting sooner cancels, after the tooltip opened it does nothing
+            on:touchend=move |_| {
+                if let Some(press) = press.get_value() {
+                    press.clear();
+                }
+            }
+        >
+            {children()}
+            <Show when=move || open.get()>
+                <span class=POPOVER_CLASS>
+                    {tip.clone()}
+                    {link
+                        .map(|href| {
+                            view! {
+                                "\n"
+                                <a href=href target="_blank" class=LINK_CLASS>
+                                    "read more"
+                                </a>
+                            }
+                        })}
+                </span>
+            </Show>
+        </span>
     }
 }
 
+/// A footer link that opens and closes a panel
 #[component]
-fn Footer(tap_data: ReadSignal<TapData>) -> impl IntoView {
-    let link_class = "hover:text-violet-400 transition-colors duration-150";
+fn Toggle(
+    label: &'static str,
+    open: ReadSignal<bool>,
+    set_open: WriteSignal<bool>,
+) -> impl IntoView {
+    view! {
+        <button
+            class=move || {
+                if open.get() { format!("text-value {LINK_CLASS}") } else { LINK_CLASS.into() }
+            }
+            on:mousedown=move |_| set_open.update(|open| *open = !*open)
+        >
+            {label}
+        </button>
+    }
+}
+
+#[component]
+fn Footer(
+    tap_data: ReadSignal<TapData>,
+    explain_open: ReadSignal<bool>,
+    set_explain_open: WriteSignal<bool>,
+    lookup_open: ReadSignal<bool>,
+    set_lookup_open: WriteSignal<bool>,
+    setup_open: ReadSignal<bool>,
+    set_setup_open: WriteSignal<bool>,
+) -> impl IntoView {
     view! {
         <span>
             "\n"
-            <span class="text-orange-400">
+            <span class="text-alert">
                 {move || if tap_data.read().is_reset() { "reset!" } else { "      " }}
-            </span> {" ".repeat(31)} <a href="https://laclark.me/blog/bpm/" class=link_class>
+            </span> {" ".repeat(4)} <Toggle label="setup" open=setup_open set_open=set_setup_open />
+            " | " <Toggle label="lookup" open=lookup_open set_open=set_lookup_open /> " | "
+            <Toggle label="explain" open=explain_open set_open=set_explain_open /> " | "
+            <a href="https://laclark.me/blog/bpm/" class=LINK_CLASS>
                 "blog"
-            </a> " | " <a href="https://github.com/lucdar/bpm/" class=link_class>
+            </a> " | " <a href="https://github.com/lucdar/bpm/" class=LINK_CLASS>
                 "source"
             </a>
         </span>
     }
 }
+
+/// Returns true if the key event is typing into a text field rather than a tap
+/// The taps of one hand or of both, with the timers that end its session
+#[derive(Clone, Copy)]
+struct Stream {
+    taps: ReadSignal<TapData>,
+    set_taps: WriteSignal<TapData>,
+    /// Wall clock time (Date.now) of the last tap, for telling sleeps from pauses
+    last_wall_ms: StoredValue<f64>,
+    reset_timeout: StoredValue<Option<TimeoutHandle>>,
+    warning_timeout: StoredValue<Option<TimeoutHandle>>,
+}
+
+impl Stream {
+    fn new(taps: ReadSignal<TapData>, set_taps: WriteSignal<TapData>) -> Self {
+        Stream {
+            taps,
+            set_taps,
+            last_wall_ms: StoredValue::new(0_f64),
+            reset_timeout: StoredValue::new(None),
+            warning_timeout: StoredValue::new(None),
+        }
+    }
+
+    fn clear_timeouts(&self) {
+        for handle in [
+            self.reset_timeout.get_value(),
+            self.warning_timeout.get_value(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            handle.clear();
+        }
+    }
+}
+
+fn is_typing(evt: &KeyboardEvent) -> bool {
+    evt.target()
+        .is_some_and(|target| target.has_type::<HtmlInputElement>())
+}
@@ -0,0 +1,162 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{MidiAccess, MidiOutput};
+
+use crate::Stepper;
+
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+const CONTROL_CHANGE: u8 = 0xB0;
+/// How long tap notes are held before their note off, in milliseconds
+const NOTE_LENGTH_MS: f64 = 50.0;
+
+/// MIDI messages sent on each tap, for hardware with tap tempo over MIDI
+/// Every number setting uses 0 to mean off
+#[derive(Clone, Copy)]
+pub struct MidiTaps {
+    access: RwSignal<Option<MidiAccess>, LocalStorage>,
+    /// 1-based index into the available outputs
+    port: RwSignal<u64>,
+    channel: RwSignal<u64>,
+    tap_cc: RwSignal<u64>,
+    tap_note: RwSignal<u64>,
+    tempo_cc: RwSignal<u64>,
+}
+
+impl MidiTaps {
+    fn output(&self) -> Option<MidiOutput> {
+        let index = self.port.get_untracked().checked_sub(1)? as usize;
+        self.access.with_untracked(|access| {
+            access
+                .as_ref()?
+                .outputs()
+                .values()
+                .into_iter()
+                .nth(index)?
+                .ok()
+                .map(|output| output.unchecked_into::<MidiOutput>())
+        })
+    }
+
+    /// Sends the configured tap messages, plus the tempo rounded to a CC value
+    pub fn on_tap(&self, bpm: Option<f64>) {
+        let Some(output) = self.output() else {
+            return;
+        };
+        let channel = (self.channel.get_untracked() as u8 - 1) & 0x0F;
+        let send = |message: [u8; 3]| {
+            let _ = output.send(&js_sys::Uint8Array::from(&message[..]));
+        };
+
+        let tap_cc = self.tap_cc.get_untracked() as u8;
+        if tap_cc > 0 {
+            send([CONTROL_CHANGE | channel, tap_cc, 127]);
+        }
+        let tap_note = self.tap_note.get_untracked() as u8;
+        if tap_note > 0 {
+            send([NOTE_ON | channel, tap_note, 127]);
+            let note_off = js_sys::Uint8Array::from(&[NOTE_OFF | channel, tap_note, 0][..]);
+            let release = window().performance().map_or(0_f64, |p| p.now()) + NOTE_LENGTH_MS;
+            let _ = output.send_with_timestamp(&note_off, release);
+        }
+        let tempo_cc = self.tempo_cc.get_untracked() as u8;
+        if let (1.., Some(bpm)) = (tempo_cc, bpm) {
+            // CC values top out at 127, so faster tempos are clamped
+            let value = bpm.round().clamp(0_f64, 127_f64) as u8;
+            send([CONTROL_CHANGE | channel, tempo_cc, value]);
+        }
+    }
+
+    fn port_name(&self) -> String {
+        self.access.track();
+        if self.port.get() == 0 {
+            return "output port, 0 is off".into();
+        }
+        if self.access.with_untracked(Option::is_none) {
+            return "waiting for midi access".into();
+        }
+        match self.output() {
+            Some(output) => output.name().unwrap_or_else(|| "unnamed port".into()),
+            None => "no such port".into(),
+        }
+    }
+}
+
+/// Sets up MIDI tap output, asking for MIDI access the first time a port is chosen
+pub fn use_midi_taps() -> MidiTaps {
+    let midi = MidiTaps {
+        access: RwSignal::new_local(None),
+        port: RwSignal::new(0),
+        channel: RwSignal::new(1),
+        // the tap tempo CC on Strymon pedals, a common choice
+        tap_cc: RwSignal::new(93),
+        tap_note: RwSignal::new(0),
+        tempo_cc: RwSignal::new(0),
+    };
+
+    Effect::new(move |requested: Option<bool>| {
+        let requested = requested.unwrap_or(false);
+        if requested || midi.port.get() == 0 {
+            return requested;
+        }
+        let Ok(request) = window().navigator().request_midi_access() else {
+            return true;
+        };
+        spawn_local(async move {
+            if let Ok(access) = JsFuture::from(request).await {
+                midi.access.set(Some(access.unchecked_into()));
+            }
+        });
+        true
+    });
+
+    midi
+}
+
+#[component]
+pub fn MidiControls(midi: MidiTaps) -> impl IntoView {
+    view! {
+        <Stepper
+            label="midi"
+            value=midi.port.read_only()
+            set_value=midi.port.write_only()
+            min=0
+            max=16
+            description=Signal::derive(move || midi.port_name())
+        />
+        <Stepper
+            label="midi-chan"
+            value=midi.channel.read_only()
+            set_value=midi.channel.write_only()
+            min=1
+            max=16
+            description="channel messages are sent on"
+        />
+        <Stepper
+            label="tap-cc"
+            value=midi.tap_cc.read_only()
+            set_value=midi.tap_cc.write_only()
+            min=0
+            max=127
+            description="cc sent on each tap, 0 is off"
+        />
+        <Stepper
+            label="tap-note"
+            value=midi.tap_note.read_only()
+            set_value=midi.tap_note.write_only()
+            min=0
+            max=127
+            description="note played on each tap"
+        />
+        <Stepper
+            label="tempo-cc"
+            value=midi.tempo_cc.read_only()
+            set_value=midi.tempo_cc.write_only()
+            min=0
+            max=127
+            description="cc set to the rounded bpm"
+        />
+    }
+}
@@ -0,0 +1,62 @@
+use leptos::ev::devicemotion;
+use leptos::prelude::*;
+use leptos_use::{use_event_listener, use_window};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::DeviceMotionEvent;
+
+use crate::onset::OnsetDetector;
+
+const GRAVITY: f64 = 9.81;
+
+/// Acceleration in m/s^2 excluding gravity, if the device reports it
+fn magnitude(evt: &DeviceMotionEvent) -> Option<f64> {
+    let norm =
+        |x: Option<f64>, y: Option<f64>, z: Option<f64>| Some((x? * x? + y? * y? + z? * z?).sqrt());
+    match evt.acceleration() {
+        Some(a) => norm(a.x(), a.y(), a.z()),
+        // some devices only report acceleration including gravity
+        None => {
+            let a = evt.acceleration_including_gravity()?;
+            norm(a.x(), a.y(), a.z()).map(|m| (m - GRAVITY).abs())
+        }
+    }
+}
+
+/// iOS only delivers motion events after `DeviceMotionEvent.requestPermission()`, which it
+/// only allows during a user gesture
+fn request_permission() {
+    let Ok(class) = js_sys::Reflect::get(&window(), &JsValue::from_str("DeviceMotionEvent")) else {
+        return;
+    };
+    let request = js_sys::Reflect::get(&class, &JsValue::from_str("requestPermission"));
+    if let Ok(request) = request.and_then(|r| r.dyn_into::<js_sys::Function>()) {
+        // the returned promise is ignored, if denied no events will arrive
+        let _ = request.call0(&class);
+    }
+}
+
+/// Asks for motion events as the sensitivity is stepped up from 0, from the click that
+/// stepped it
+pub fn on_step(sensitivity: u64) {
+    if sensitivity == 1 {
+        request_permission();
+    }
+}
+
+/// Calls `on_beat` whenever the device is jolted harder than the threshold set by
+/// `sensitivity`, from 1 (hard knocks only) to 9 (gentle bounces); 0 disables it
+pub fn use_motion_beats(sensitivity: ReadSignal<u64>, on_beat: impl Fn() + 'static) {
+    let mut detector = OnsetDetector::default();
+    let _cleanup = use_event_listener(use_window(), devicemotion, move |evt: DeviceMotionEvent| {
+        let sensitivity = sensitivity.get_untracked();
+        if sensitivity == 0 {
+            return;
+        }
+        let threshold = 2_f64 * (10 - sensitivity) as f64;
+        if let Some(magnitude) = magnitude(&evt) {
+            if detector.sample(magnitude, threshold, evt.time_stamp()) {
+                on_beat();
+            }
+        }
+    });
+}
@@ -0,0 +1,37 @@
+/// Largest term considered when expressing a tempo ratio, so 7:4 is found but not 17:12
+const MAX_RATIO_TERM: u32 = 9;
+
+/// The simplest ratio `p:q` closest to `a / b`, both terms at most `MAX_RATIO_TERM`
+pub fn nearest_ratio(a: f64, b: f64) -> (u32, u32) {
+    let target = a / b;
+    let mut best = (1, 1);
+    let mut best_error = f64::INFINITY;
+    // walking by increasing q keeps the first (simplest) of equally good ratios
+    for q in 1..=MAX_RATIO_TERM {
+        for p in 1..=MAX_RATIO_TERM {
+            let error = (p as f64 / q as f64 / target).ln().abs();
+            if error < best_error - 1e-9 {
+                best = (p, q);
+                best_error = error;
+            }
+        }
+    }
+    best
+}
+
+/// Metric modulations as `(p, q, name)`, where `p` new beats fill the time of `q` old ones
+pub const MODULATIONS: [(u32, u32, &str); 8] = [
+    (1, 2, "half time"),
+    (2, 1, "double time"),
+    (2, 3, "dotted beat is the beat"),
+    (3, 2, "triplet is the beat"),
+    (3, 4, "half note triplet"),
+    (4, 3, "dotted 8th is the beat"),
+    (4, 5, "4 over 5"),
+    (5, 4, "quintuplet is the beat"),
+];
+
+/// The tempo reached by modulating `bpm` so `p` new beats fill the time of `q` old ones
+pub fn modulate(bpm: f64, p: u32, q: u32) -> f64 {
+    bpm * p as f64 / q as f64
+}
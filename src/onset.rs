@@ -0,0 +1,55 @@
+/// Minimum milliseconds between onsets, so one impact or flash isn't counted twice
+const REFRACTORY_MS: f64 = 120.0;
+
+/// Turns a stream of detector samples into discrete beats, shared by every
+/// sensor-driven input
+#[derive(Default)]
+pub struct OnsetDetector {
+    above: bool,
+    last_onset: Option<f64>,
+}
+
+impl OnsetDetector {
+    /// Returns true if this sample crosses the threshold and starts a new beat
+    pub fn sample(&mut self, value: f64, threshold: f64, time_ms: f64) -> bool {
+        let was_above = std::mem::replace(&mut self.above, value > threshold);
+        let rested = self
+            .last_onset
+            .is_none_or(|last| time_ms - last >= REFRACTORY_MS);
+        if self.above && !was_above && rested {
+            self.last_onset = Some(time_ms);
+            return true;
+        }
+        false
+    }
+}
+
+/// Flux below this is treated as silence, so noise floors don't look like onsets
+const MIN_FLUX: f64 = 0.5;
+/// Weight of each new frame in the running average of flux
+const FLUX_SMOOTHING: f64 = 0.02;
+
+/// Measures how much a spectrum grew since the previous frame, the usual
+/// novelty function for picking note onsets out of audio
+#[derive(Default)]
+pub struct SpectralFlux {
+    previous: Vec<u8>,
+    average: f64,
+}
+
+impl SpectralFlux {
+    /// Returns the flux of `spectrum` relative to the running average, 1.0 being typical
+    pub fn sample(&mut self, spectrum: &[u8]) -> f64 {
+        let growth: u32 = spectrum
+            .iter()
+            .zip(&self.previous)
+            .map(|(&now, &before)| now.saturating_sub(before) as u32)
+            .sum();
+        let flux = growth as f64 / spectrum.len().max(1) as f64;
+        let relative = flux / self.average.max(MIN_FLUX);
+        self.average += (flux - self.average) * FLUX_SMOOTHING;
+        self.previous.clear();
+        self.previous.extend_from_slice(spectrum);
+        relative
+    }
+}
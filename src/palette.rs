@@ -0,0 +1,121 @@
+use leptos::html::Input;
+use leptos::prelude::*;
+
+/// Matches listed under the search box at once
+const MAX_MATCHES: usize = 6;
+
+/// Something the palette can run, named the way it is searched for
+pub struct PaletteCommand {
+    name: String,
+    run: Box<dyn Fn()>,
+}
+
+pub fn command(name: impl Into<String>, run: impl Fn() + 'static) -> PaletteCommand {
+    PaletteCommand {
+        name: name.into(),
+        run: Box::new(run),
+    }
+}
+
+/// How far `name` is from `query`, if every letter of the query is in it in order
+/// Each letter of the name skipped on the way costs one, so tighter matches come first
+fn fuzzy_cost(query: &str, name: &str) -> Option<usize> {
+    let mut name = name.chars();
+    let mut cost = 0;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let wanted = wanted.to_ascii_lowercase();
+        while name.next()?.to_ascii_lowercase() != wanted {
+            cost += 1;
+        }
+    }
+    Some(cost)
+}
+
+/// Indices of the commands matching `query`, best first
+fn matches(commands: &[PaletteCommand], query: &str) -> Vec<usize> {
+    let mut found: Vec<_> = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(index, command)| Some((fuzzy_cost(query, &command.name)?, index)))
+        .collect();
+    found.sort_by_key(|&(cost, _)| cost);
+    found.into_iter().map(|(_, index)| index).collect()
+}
+
+/// Searches every command by name, running the chosen one and closing
+#[component]
+pub fn CommandPalette(set_open: WriteSignal<bool>, commands: Vec<PaletteCommand>) -> impl IntoView {
+    let commands = StoredValue::new_local(commands);
+    let (query, set_query) = signal(String::new());
+    let (selected, set_selected) = signal(0_usize);
+    let found = Memo::new(move |_| {
+        let mut found = commands.with_value(|commands| matches(commands, &query.read()));
+        found.truncate(MAX_MATCHES);
+        found
+    });
+    let run = move |index: usize| {
+        commands.with_value(|commands| (commands[index].run)());
+        set_open.set(false);
+    };
+
+    let input = NodeRef::<Input>::new();
+    Effect::new(move |_| {
+        if let Some(input) = input.get() {
+            let _ = input.focus();
+        }
+    });
+
+    view! {
+        <span class="text-label">"     command:  "</span>
+        <input
+            node_ref=input
+            class="bg-field outline-none w-[20ch] text-value"
+            placeholder="type to search"
+            on:input=move |evt| {
+                set_query.set(event_target_value(&evt));
+                set_selected.set(0);
+            }
+            on:keydown=move |evt| {
+                let last = found.read().len().saturating_sub(1);
+                match evt.key().as_str() {
+                    "Enter" => {
+                        if let Some(&index) = found.read().get(selected.get()) {
+                            run(index);
+                        }
+                    }
+                    "ArrowDown" => set_selected.update(|s| *s = (*s + 1).min(last)),
+                    "ArrowUp" => set_selected.update(|s| *s = s.saturating_sub(1)),
+                    "Escape" => set_open.set(false),
+                    "k" if evt.ctrl_key() => set_open.set(false),
+                    _ => return,
+                }
+                evt.prevent_default();
+            }
+        />
+        <span class="text-comment">" # enter runs, esc closes\n"</span>
+        {move || {
+            found
+                .get()
+                .into_iter()
+                .enumerate()
+                .map(|(rank, index)| {
+                    let name = commands.with_value(|commands| commands[index].name.clone());
+                    let marker = move || if selected.get() == rank { ">" } else { "" };
+                    view! {
+                        <span class="text-value">{move || format!("{:>12}  ", marker())}</span>
+                        <button
+                            class="text-comment hover:text-value"
+                            on:mousedown=move |_| run(index)
+                        >
+                            {name}
+                        </button>
+                        "\n"
+                    }
+                })
+                .collect_view()
+        }}
+        <Show when=move || found.read().is_empty()>
+            <span class="text-comment">{format!("{:>14}no matching commands\n", "")}</span>
+        </Show>
+    }
+}
@@ -0,0 +1,101 @@
+use leptos::prelude::*;
+
+use crate::Stepper;
+
+/// Re-arm times are set in steps of this many milliseconds
+const REARM_STEP_MS: u64 = 50;
+
+#[derive(Debug, Default)]
+struct SwitchState {
+    held: bool,
+    /// When the switch was last let go, in ms
+    released: Option<f64>,
+}
+
+impl SwitchState {
+    /// Returns true if a press at `time_ms` is a tap, ignoring auto-repeats, presses while
+    /// still held down, and presses within `rearm_ms` of letting go, which a bouncing
+    /// contact makes
+    fn press(&mut self, time_ms: f64, repeat: bool, rearm_ms: f64) -> bool {
+        if repeat || self.held {
+            return false;
+        }
+        self.held = true;
+        self.released
+            .is_none_or(|released| time_ms - released >= rearm_ms)
+    }
+
+    fn release(&mut self, time_ms: f64) {
+        self.held = false;
+        self.released = Some(time_ms);
+    }
+}
+
+/// Input shaping for footswitches and other switch-like inputs, which repeat while held,
+/// stay down for long and bounce when they close
+#[derive(Clone, Copy)]
+pub struct Pedal {
+    /// 0 leaves the input as is, otherwise presses re-arm this many steps after letting go
+    steps: RwSignal<u64>,
+    state: StoredValue<SwitchState>,
+}
+
+impl Pedal {
+    /// Returns true if a press at `time_ms` counts as a tap
+    pub fn press(&self, time_ms: f64, repeat: bool) -> bool {
+        let rearm_ms = self.steps.get_untracked() * REARM_STEP_MS;
+        if rearm_ms == 0 {
+            return true;
+        }
+        let mut state = self.state.write_value();
+        state.press(time_ms, repeat, rearm_ms as f64)
+    }
+
+    pub fn release(&self, time_ms: f64) {
+        self.state.write_value().release(time_ms);
+    }
+}
+
+pub fn use_pedal() -> Pedal {
+    Pedal {
+        steps: RwSignal::new(0),
+        state: StoredValue::new(SwitchState::default()),
+    }
+}
+
+/// The re-arm setting of one input source, under `label`
+#[component]
+pub fn PedalControls(label: &'static str, pedal: Pedal) -> impl IntoView {
+    view! {
+        <Stepper
+            label
+            value=pedal.steps.read_only()
+            set_value=pedal.steps.write_only()
+            min=0
+            max=9
+            description=Signal::derive(move || match pedal.steps.get() * REARM_STEP_MS {
+                0 => "every press is a tap".to_string(),
+                ms => format!("release, then {ms}ms to re-arm"),
+            })
+        />
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_presses_rearm() {
+        let mut state = SwitchState::default();
+        assert!(state.press(0_f64, false, 100_f64));
+        // held down, and repeating while held
+        assert!(!state.press(10_f64, false, 100_f64));
+        assert!(!state.press(500_f64, true, 100_f64));
+        // the contact bouncing as it is let go
+        state.release(600_f64);
+        assert!(!state.press(620_f64, false, 100_f64));
+        state.release(625_f64);
+        assert!(state.press(800_f64, false, 100_f64));
+    }
+}
@@ -0,0 +1,73 @@
+use leptos::prelude::*;
+
+use crate::bpm;
+use crate::music::nearest_ratio;
+use crate::TapData;
+
+/// Keys played by the left hand on a QWERTY keyboard, by `KeyboardEvent.code`
+const LEFT_HAND: [&str; 21] = [
+    "Backquote",
+    "Digit1",
+    "Digit2",
+    "Digit3",
+    "Digit4",
+    "Digit5",
+    "KeyQ",
+    "KeyW",
+    "KeyE",
+    "KeyR",
+    "KeyT",
+    "KeyA",
+    "KeyS",
+    "KeyD",
+    "KeyF",
+    "KeyG",
+    "KeyZ",
+    "KeyX",
+    "KeyC",
+    "KeyV",
+    "KeyB",
+];
+
+/// Returns true if the key with this `KeyboardEvent.code` belongs to the left stream
+pub fn is_left_hand(code: &str) -> bool {
+    LEFT_HAND.contains(&code)
+}
+
+fn tempo(taps: ReadSignal<TapData>) -> Option<f64> {
+    bpm::thiel_sen(&taps.read().timestamps()).ok()
+}
+
+/// Tempos of the left and right hand streams and how they relate
+#[component]
+pub fn PolyTable(left: ReadSignal<TapData>, right: ReadSignal<TapData>) -> impl IntoView {
+    let row = move |label: &'static str, taps: ReadSignal<TapData>| {
+        view! {
+            <span class="text-label">{format!("{label:>12}: ")}</span>
+            <span class="text-value">
+                {move || match tempo(taps) {
+                    Some(bpm) => format!("{bpm:6.2} "),
+                    None => "000.00 ".into(),
+                }}
+            </span>
+            <span class="text-comment">"# "{move || taps.read().len()}" taps\n"</span>
+        }
+    };
+
+    view! {
+        "\n"
+        {row("left", left)}
+        {row("right", right)}
+        <span class="text-label">{format!("{:>12}: ", "ratio")}</span>
+        <span class="text-value">
+            {move || match (tempo(left), tempo(right)) {
+                (Some(l), Some(r)) => {
+                    let (p, q) = nearest_ratio(l, r);
+                    format!("{:>6} ", format!("{p}:{q}"))
+                }
+                _ => "   -:- ".into(),
+            }}
+        </span>
+        <span class="text-comment">"# left to right\n"</span>
+    }
+}
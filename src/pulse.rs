@@ -0,0 +1,119 @@
+use leptos::prelude::*;
+use leptos_use::{use_prefers_reduced_motion, use_raf_fn};
+use web_time::Instant;
+
+use crate::bpm::{self, median, LineFit};
+use crate::{watchdog, TapData};
+
+/// Opacity of the glow at the top of a beat, with the taps right on the grid
+const PEAK_OPACITY: f64 = 0.35;
+/// Taps landing this share of a beat off the grid, typically, leave no confidence at all
+const LOST_SHARE: f64 = 0.25;
+
+/// How far the grid can be trusted, from 1 with every tap on it to 0 once the typical tap
+/// lands `LOST_SHARE` of a beat away
+fn confidence(offsets: &[u64], fit: &LineFit) -> f64 {
+    let period = 1_f64 / fit.slope;
+    let errors = offsets
+        .iter()
+        .enumerate()
+        .map(|(y, &x)| (x as f64 - fit.offset_of(y as f64)).abs())
+        .collect();
+    (1_f64 - median(errors) / (LOST_SHARE * period)).clamp(0_f64, 1_f64)
+}
+
+/// Opacity of the glow `beat` beats into the session, brightest on the beat and fading
+/// until the next
+fn glow(beat: f64, confidence: f64) -> f64 {
+    let fade = 1_f64 - beat.rem_euclid(1_f64);
+    PEAK_OPACITY * confidence * fade * fade
+}
+
+/// The thiel-sen grid of the taps and where the session started, from which the beat at
+/// any moment follows
+#[derive(Clone, Copy)]
+pub struct BeatClock {
+    anchor: StoredValue<Option<Instant>>,
+    pub fit: Memo<Option<LineFit>>,
+}
+
+impl BeatClock {
+    /// The grid and the milliseconds since the session started, without tracking either
+    pub fn now(&self) -> Option<(LineFit, f64)> {
+        let (fit, start) = (self.fit.get_untracked()?, self.anchor.get_value()?);
+        let elapsed_ms = Instant::now().duration_since(start).as_secs_f64() * 1_000_f64;
+        Some((fit, elapsed_ms))
+    }
+}
+
+/// Keeps the beat grid of `offsets` anchored to the start of the session in `tap_data`
+pub fn use_beat_clock(tap_data: ReadSignal<TapData>, offsets: Memo<Vec<u64>>) -> BeatClock {
+    // the grid is measured from the session's start, which a reset forgets
+    let anchor = StoredValue::new(None::<Instant>);
+    Effect::new(move |_| {
+        if let Some(start) = tap_data.read().start {
+            anchor.set_value(Some(start));
+        }
+    });
+    let fit = Memo::new(move |_| {
+        let fit = bpm::thiel_sen_fit(&offsets.read()).ok()?;
+        fit.slope.is_finite().then_some(fit)
+    });
+    BeatClock { anchor, fit }
+}
+
+/// A glow over the whole window that pulses on the beat of `clock`, fainter the less the
+/// `offsets` it was fit to agree with it, and left out for anyone who prefers reduced motion
+#[component]
+pub fn BeatPulse(clock: BeatClock, offsets: Memo<Vec<u64>>) -> impl IntoView {
+    let confidence = Memo::new(move |_| {
+        let fit = clock.fit.get()?;
+        Some(confidence(&offsets.read(), &fit))
+    });
+
+    let opacity = RwSignal::new(0_f64);
+    let raf = use_raf_fn(move |_| {
+        let glow = match (clock.now(), confidence.get_untracked()) {
+            (Some((fit, elapsed_ms)), Some(confidence)) => {
+                glow(fit.beat_at(elapsed_ms), confidence)
+            }
+            _ => 0_f64,
+        };
+        opacity.set(glow);
+    });
+    let reduced_motion = use_prefers_reduced_motion();
+    let idle = watchdog::idle();
+    Effect::new(move |_| {
+        if idle.get() || reduced_motion.get() {
+            (raf.pause)();
+        } else {
+            (raf.resume)();
+        }
+    });
+
+    view! {
+        <Show when=move || !reduced_motion.get()>
+            <div
+                class="fixed inset-0 pointer-events-none"
+                style:background="radial-gradient(circle, rgb(var(--value)), transparent 70%)"
+                style:opacity=move || format!("{:.3}", opacity.get())
+            />
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glows_on_the_beat() {
+        let fit = bpm::thiel_sen_fit(&[0, 500, 1000, 1500]).unwrap();
+        assert_eq!(confidence(&[0, 500, 1000, 1500], &fit), 1_f64);
+        // taps a quarter beat off leave nothing to go on
+        assert_eq!(confidence(&[0, 625, 1000, 1625, 2000, 2625], &fit), 0_f64);
+        assert_eq!(glow(3_f64, 1_f64), PEAK_OPACITY);
+        assert_eq!(glow(2.5, 1_f64), PEAK_OPACITY / 4_f64);
+        assert_eq!(glow(-0.5, 0.5), PEAK_OPACITY / 8_f64);
+    }
+}
@@ -0,0 +1,100 @@
+use leptos::prelude::*;
+
+use crate::bpm::{self, BpmCalculationError};
+use crate::{rate, Stepper, Tooltip};
+
+/// The inlier threshold is set in steps of this many milliseconds, and the lines tried in
+/// steps of this many
+const STEP_MS: u64 = 10;
+const RUNS_STEP: u64 = 50;
+
+/// How far off the line a tap can land and still agree with it, and how many lines are tried
+#[derive(Clone, Copy)]
+pub struct Ransac {
+    steps: RwSignal<u64>,
+    runs: RwSignal<u64>,
+}
+
+impl Ransac {
+    fn threshold_ms(&self) -> u64 {
+        self.steps.get() * STEP_MS
+    }
+
+    fn iterations(&self) -> u64 {
+        self.runs.get() * RUNS_STEP
+    }
+}
+
+pub fn use_ransac() -> Ransac {
+    Ransac {
+        steps: RwSignal::new(4),
+        runs: RwSignal::new(2),
+    }
+}
+
+#[component]
+pub fn RansacControls(ransac: Ransac) -> impl IntoView {
+    let threshold = Signal::derive(move || format!("inliers within {}ms", ransac.threshold_ms()));
+    let lines = Signal::derive(move || format!("at most {} lines tried", ransac.iterations()));
+
+    view! {
+        <Stepper
+            label="ransac-ms"
+            value=ransac.steps.read_only()
+            set_value=ransac.steps.write_only()
+            min=1
+            max=20
+            description=threshold
+        />
+        <Stepper
+            label="ransac-runs"
+            value=ransac.runs.read_only()
+            set_value=ransac.runs.write_only()
+            min=1
+            max=20
+            description=lines
+        />
+    }
+}
+
+/// The RANSAC estimate of `offsets`, with how many taps agreed on it
+#[component]
+pub fn RansacMetric(
+    offsets: Memo<Vec<u64>>,
+    ransac: Ransac,
+    slow: ReadSignal<bool>,
+    /// Estimates over this are flagged rather than shown, 0 shows them all
+    max_bpm: Signal<u64>,
+) -> impl IntoView {
+    let consensus = Memo::new(move |_| {
+        let (threshold_ms, iterations) = (ransac.threshold_ms(), ransac.iterations());
+        bpm::ransac(&offsets.read(), threshold_ms as f64, iterations as usize)
+    });
+    let estimate = Memo::new(move |_| {
+        consensus
+            .get()
+            .and_then(|consensus| bpm::finite(consensus.bpm))
+            .and_then(|bpm| bpm::plausible(bpm, max_bpm.get()))
+    });
+    // a missing estimate is expected before the second tap, anything else is noted
+    let note = move || match (estimate.get(), consensus.get()) {
+        (Err(e), _) if e != BpmCalculationError::InsufficientData => e.to_string(),
+        (_, Ok(consensus)) => format!("lin-reg of {} agreeing taps", consensus.inliers),
+        _ => "lin-reg of the taps that agree".to_string(),
+    };
+
+    view! {
+        <span class="text-label">{format!("{:>12}: ", "ransac")}</span>
+        <span class="text-value">{move || rate::format_rate(estimate.get().ok(), slow.get())}</span>
+        <span class="text-comment">
+            "# "
+            <Tooltip
+                lines=vec!["best of lines through two taps", "by taps within ransac-ms of it"]
+                link=None
+            >
+                {note}
+            </Tooltip>
+            "\n"
+        </span>
+    }
+}
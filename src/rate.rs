@@ -0,0 +1,15 @@
+/// Below this many events per minute, slow mode counts per hour instead
+const PER_HOUR_BELOW: f64 = 1.0;
+
+/// Formats a rate in events per minute to fill the table's value column
+/// Slow mode is for breaths, reps, or machine cycles, where decimals are noise:
+/// it rounds to whole events and switches to per-hour units for very slow streams
+pub fn format_rate(per_minute: Option<f64>, slow: bool) -> String {
+    match (per_minute, slow) {
+        (None, false) => "000.00 ".into(),
+        (None, true) => "   0/m ".into(),
+        (Some(rate), false) => format!("{rate:6.2} "),
+        (Some(rate), true) if rate < PER_HOUR_BELOW => format!("{:>4.0}/h ", rate * 60_f64),
+        (Some(rate), true) => format!("{rate:>4.0}/m "),
+    }
+}
@@ -0,0 +1,220 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use leptos::logging::error;
+use leptos::prelude::*;
+use leptos_use::{use_user_media_with_options, UseUserMediaOptions, UseUserMediaReturn};
+use serde::Serialize;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobEvent, BlobPropertyBag, MediaRecorder, MediaStream};
+
+use crate::{export, schema};
+
+/// Name the audio is saved under, before its extension
+const STEM: &str = "recording";
+
+/// A microphone recording, with the handlers that have to live as long as it does
+struct Recording {
+    recorder: MediaRecorder,
+    _on_data: Closure<dyn FnMut(BlobEvent)>,
+    _on_start: Closure<dyn FnMut()>,
+    _on_stop: Closure<dyn FnMut()>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecorderState {
+    Off,
+    /// Waiting on the microphone, which may mean a permission prompt
+    Asking,
+    Recording,
+}
+
+/// Records the microphone while tapping, saving the audio with the taps timed against it
+#[derive(Clone, Copy)]
+pub struct SessionRecorder {
+    set_microphone: WriteSignal<bool>,
+    state: RwSignal<RecorderState>,
+    recording: StoredValue<Option<Recording>, LocalStorage>,
+    /// Milliseconds (performance.now) at which the audio started
+    started_at: RwSignal<Option<f64>>,
+    /// Milliseconds into the audio of each tap
+    taps: RwSignal<Vec<f64>>,
+}
+
+/// The taps saved next to a recording, in seconds from the start of its audio
+#[derive(Serialize)]
+struct RecordedTaps {
+    audio: String,
+    taps: Vec<f64>,
+}
+
+impl SessionRecorder {
+    /// Notes a tap made at `time_ms`, if a recording is running
+    pub fn on_tap(&self, time_ms: f64) {
+        if let Some(start) = self.started_at.get_untracked() {
+            self.taps.write().push(time_ms - start);
+        }
+    }
+
+    fn toggle(&self) {
+        match self.state.get_untracked() {
+            RecorderState::Off => {
+                self.state.set(RecorderState::Asking);
+                self.set_microphone.set(true);
+            }
+            RecorderState::Asking => self.finish(),
+            // the audio is saved once the recorder hands over its last chunk
+            RecorderState::Recording => self.recording.with_value(|recording| {
+                if let Some(recording) = recording {
+                    let _ = recording.recorder.stop();
+                }
+            }),
+        }
+    }
+
+    fn begin(&self, stream: &MediaStream) -> Result<(), JsValue> {
+        let recorder = MediaRecorder::new_with_media_stream(stream)?;
+        let chunks = Rc::new(RefCell::new(Vec::<Blob>::new()));
+
+        let on_data = {
+            let chunks = Rc::clone(&chunks);
+            Closure::<dyn FnMut(BlobEvent)>::new(move |evt: BlobEvent| {
+                chunks.borrow_mut().extend(evt.data());
+            })
+        };
+        let on_start = {
+            let session = *self;
+            Closure::<dyn FnMut()>::new(move || {
+                session.taps.write().clear();
+                session
+                    .started_at
+                    .set(Some(window().performance().map_or(0_f64, |p| p.now())));
+                session.state.set(RecorderState::Recording);
+            })
+        };
+        let on_stop = {
+            let (session, recorder) = (*self, recorder.clone());
+            Closure::<dyn FnMut()>::new(move || {
+                let parts = chunks.borrow_mut().drain(..).collect::<js_sys::Array>();
+                let mime = recorder.mime_type();
+                let options = BlobPropertyBag::new();
+                options.set_type(&mime);
+                if let Ok(audio) = Blob::new_with_blob_sequence_and_options(&parts, &options) {
+                    session.save(&audio, &mime);
+                }
+                session.finish();
+            })
+        };
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+        recorder.set_onstart(Some(on_start.as_ref().unchecked_ref()));
+        recorder.set_onstop(Some(on_stop.as_ref().unchecked_ref()));
+        recorder.start()?;
+        self.recording.set_value(Some(Recording {
+            recorder,
+            _on_data: on_data,
+            _on_start: on_start,
+            _on_stop: on_stop,
+        }));
+        Ok(())
+    }
+
+    fn save(&self, audio: &Blob, mime: &str) {
+        let audio_name = format!("{STEM}.{}", extension(mime));
+        let taps = RecordedTaps {
+            audio: audio_name.clone(),
+            taps: self
+                .taps
+                .read_untracked()
+                .iter()
+                .map(|t| t / 1_000_f64)
+                .collect(),
+        };
+        export::download_blob(&audio_name, audio);
+        export::download_text(
+            &format!("{STEM}-taps.json"),
+            "application/json",
+            &schema::to_json(&taps),
+        );
+    }
+
+    /// Lets go of the microphone
+    fn finish(&self) {
+        self.set_microphone.set(false);
+        self.started_at.set(None);
+        self.state.set(RecorderState::Off);
+    }
+}
+
+/// File extension for audio of type `mime`, which browsers pick for themselves
+fn extension(mime: &str) -> &'static str {
+    let container = mime.split(';').next().unwrap_or_default().trim();
+    match container {
+        "audio/ogg" => "ogg",
+        "audio/mp4" => "m4a",
+        _ => "webm",
+    }
+}
+
+pub fn use_session_recorder() -> SessionRecorder {
+    let UseUserMediaReturn {
+        stream,
+        set_enabled,
+        ..
+    } = use_user_media_with_options(UseUserMediaOptions::default().video(false).audio(true));
+    let session = SessionRecorder {
+        set_microphone: set_enabled,
+        state: RwSignal::new(RecorderState::Off),
+        recording: StoredValue::new_local(None),
+        started_at: RwSignal::new(None),
+        taps: RwSignal::new(Vec::new()),
+    };
+
+    Effect::new(move |_| match stream.get() {
+        Some(Ok(stream)) if session.state.get_untracked() == RecorderState::Asking => {
+            if let Err(e) = session.begin(&stream) {
+                error!("recording unavailable: {e:?}");
+                session.finish();
+            }
+        }
+        Some(Err(e)) => {
+            error!("microphone unavailable: {e:?}");
+            session.finish();
+        }
+        _ => {}
+    });
+
+    session
+}
+
+#[component]
+pub fn RecorderControls(recorder: SessionRecorder) -> impl IntoView {
+    let status = move || match recorder.state.get() {
+        RecorderState::Off => "saves mic audio with the taps".to_string(),
+        RecorderState::Asking => "waiting on the microphone".to_string(),
+        RecorderState::Recording => format!("{} taps, stop saves", recorder.taps.read().len()),
+    };
+
+    view! {
+        <span class="text-label">"      record:  "</span>
+        <button class="hover:text-value" on:mousedown=move |_| recorder.toggle()>
+            {move || {
+                if recorder.state.get() == RecorderState::Off { "start" } else { "stop " }
+            }}
+        </button>
+        <span class="text-comment">" # "{status}"\n"</span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_follows_the_container() {
+        assert_eq!(extension("audio/webm;codecs=opus"), "webm");
+        assert_eq!(extension("audio/ogg; codecs=opus"), "ogg");
+        assert_eq!(extension("audio/mp4"), "m4a");
+        assert_eq!(extension(""), "webm");
+    }
+}
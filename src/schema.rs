@@ -0,0 +1,49 @@
+//! The one serialized form for everything the app saves, exports or hands to other code
+//!
+//! Documents are JSON objects carrying a `version` next to their own fields, so a saved
+//! session in version 1 reads `{"version":1,"timestamps":[0,500,1000]}`. Fields may be added
+//! within a version, but renaming or removing one means a new version.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("written by a newer version (schema {0})")]
+    Unsupported(u32),
+    #[error("malformed document: {0}")]
+    Malformed(#[from] serde_json::Error),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    #[serde(flatten)]
+    data: T,
+}
+
+pub fn to_json<T: Serialize>(data: &T) -> String {
+    serde_json::to_string(&Versioned {
+        version: SCHEMA_VERSION,
+        data,
+    })
+    .expect("app types serialize to JSON")
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, SchemaError> {
+    // the version is checked first, newer documents may not parse as the current types
+    let Header { version } = serde_json::from_str(json)?;
+    if version > SCHEMA_VERSION {
+        return Err(SchemaError::Unsupported(version));
+    }
+    let document: Versioned<T> = serde_json::from_str(json)?;
+    Ok(document.data)
+}
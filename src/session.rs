@@ -0,0 +1,282 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use web_time::{Duration, Instant};
+
+use crate::history;
+
+/// No pause in the music lasts this long, so a gap this long is the device sleeping
+const MAX_GAP: Duration = Duration::from_secs(60 * 60);
+/// The wall clock getting this far ahead of the monotonic one means the device slept
+const SLEEP_SKEW: Duration = Duration::from_secs(5);
+
+/// What the time between two taps means for the session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gap {
+    /// The session goes on
+    Beat,
+    /// The reset time passed, so the session is over
+    Reset,
+    /// The device slept or the clock jumped, so the session is over and the gap is no beat
+    Suspend,
+}
+
+/// The taps of one session
+/// Taps are kept as the milliseconds since the tap before, which fit in a u32 and take half
+/// the memory of absolute offsets; `timestamps` rebuilds the offsets estimators work on
+#[derive(Default)]
+pub struct TapData {
+    pub start: Option<Instant>,
+    /// The first delta is the offset of the first tap, usually 0
+    deltas: Vec<u32>,
+    /// Offset of the last tap, so new taps don't have to sum every delta
+    last: u64,
+    pub history: history::History,
+}
+
+impl TapData {
+    /// Appends a new tap datapoint to the end of the vector
+    /// If self.start is None, records `now` as the start and resets timestamps
+    pub fn record(&mut self, now: Instant) {
+        match self.start {
+            Some(start) => self.push_offset(now.duration_since(start).as_millis() as u64),
+            None => {
+                self.start = Some(now);
+                self.set_timestamps(&[0]);
+            }
+        }
+    }
+    /// Replaces the session with a finished one made of `timestamps`
+    pub fn load(&mut self, timestamps: Vec<u64>) {
+        self.start = None;
+        self.set_timestamps(&timestamps);
+        // recorded commands no longer apply to the new session
+        self.history = history::History::default();
+    }
+    /// Returns true if the bpm count has been reset
+    pub fn is_reset(&self) -> bool {
+        self.start.is_none() && !self.deltas.is_empty()
+    }
+    /// Returns true if no tap events have ever been recorded
+    pub fn has_no_taps(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Time from the last tap of a running session to `now`
+    pub fn since_last_tap(&self, now: Instant) -> Option<Duration> {
+        let start = self.start?;
+        Some(now.duration_since(start + Duration::from_millis(self.last)))
+    }
+
+    /// What the gap from the last tap to one made at `now` means, given `wall`, the time
+    /// the wall clock says has passed since the last tap
+    pub fn gap(&self, now: Instant, wall: Duration, reset_after: Duration) -> Gap {
+        let Some(gap) = self.since_last_tap(now) else {
+            return Gap::Beat;
+        };
+        // monotonic clocks may stop while the device sleeps, the wall clock doesn't
+        if gap >= MAX_GAP || wall.saturating_sub(gap) >= SLEEP_SKEW {
+            Gap::Suspend
+        } else if gap >= reset_after {
+            Gap::Reset
+        } else {
+            Gap::Beat
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Milliseconds from the start of the session to each tap
+    pub fn offsets(&self) -> impl Iterator<Item = u64> + '_ {
+        self.deltas.iter().scan(0_u64, |offset, &delta| {
+            *offset += delta as u64;
+            Some(*offset)
+        })
+    }
+
+    pub fn timestamps(&self) -> Vec<u64> {
+        self.offsets().collect()
+    }
+
+    /// Replaces the taps, leaving the start and undo history alone
+    pub fn set_timestamps(&mut self, timestamps: &[u64]) {
+        self.deltas.clear();
+        self.last = 0;
+        for &offset in timestamps {
+            self.push_offset(offset);
+        }
+    }
+
+    /// Adds a tap at `offset`, clamped to the last tap if it comes before it and to the
+    /// longest gap a delta holds if it comes after that
+    fn push_offset(&mut self, offset: u64) {
+        let delta = u32::try_from(offset.saturating_sub(self.last)).unwrap_or(u32::MAX);
+        self.deltas.push(delta);
+        self.last += delta as u64;
+    }
+
+    /// Removes the last tap
+    pub fn pop_tap(&mut self) {
+        if let Some(delta) = self.deltas.pop() {
+            self.last -= delta as u64;
+        }
+    }
+
+    /// Removes tap `index`
+    pub fn remove_tap(&mut self, index: usize) {
+        let mut timestamps = self.timestamps();
+        if index < timestamps.len() {
+            timestamps.remove(index);
+            self.set_timestamps(&timestamps);
+        }
+    }
+
+    /// Moves tap `index` by `ms`, no further than the taps either side of it
+    pub fn nudge_tap(&mut self, index: usize, ms: i64) {
+        let mut timestamps = self.timestamps();
+        let Some(&offset) = timestamps.get(index) else {
+            return;
+        };
+        let earliest = index.checked_sub(1).map_or(0, |before| timestamps[before]);
+        let latest = timestamps.get(index + 1).copied().unwrap_or(u64::MAX);
+        timestamps[index] = offset.saturating_add_signed(ms).clamp(earliest, latest);
+        self.set_timestamps(&timestamps);
+    }
+
+    /// Bytes held for the taps
+    pub fn memory_bytes(&self) -> usize {
+        self.deltas.capacity() * size_of::<u32>()
+    }
+}
+
+/// Returns true if `timestamps` are in order with every gap short enough to keep as a delta,
+/// about 49 days, so they load as they are
+pub fn fits(timestamps: &[u64]) -> bool {
+    timestamps
+        .windows(2)
+        .all(|pair| pair[0] <= pair[1] && u32::try_from(pair[1] - pair[0]).is_ok())
+}
+
+/// Sessions are saved as absolute offsets, the delta encoding is only kept in memory
+#[derive(Serialize, Deserialize)]
+struct SavedTaps {
+    timestamps: Vec<u64>,
+}
+
+impl Serialize for TapData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let timestamps = self.timestamps();
+        SavedTaps { timestamps }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TapData {
+    // a restored session is always a finished one
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SavedTaps { timestamps } = SavedTaps::deserialize(deserializer)?;
+        if !fits(&timestamps) {
+            return Err(serde::de::Error::custom(
+                "taps out of order or too far apart",
+            ));
+        }
+        let mut taps = TapData::default();
+        taps.load(timestamps);
+        Ok(taps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_gaps() {
+        let start = Instant::now();
+        let reset_after = Duration::from_secs(2);
+        let gap = |taps: &TapData, ms: u64, wall_ms: u64| {
+            let now = start + Duration::from_millis(ms);
+            taps.gap(now, Duration::from_millis(wall_ms - 1500), reset_after)
+        };
+        let mut taps = TapData::default();
+        assert_eq!(gap(&taps, 1500, 1500), Gap::Beat);
+        taps.record(start);
+        taps.record(start + Duration::from_millis(1500));
+        assert_eq!(gap(&taps, 3000, 3000), Gap::Beat);
+        assert_eq!(gap(&taps, 3500, 3500), Gap::Reset);
+        // the monotonic clock stood still while the wall clock ran on
+        assert_eq!(gap(&taps, 2000, 60_000), Gap::Suspend);
+        assert_eq!(
+            gap(&taps, 2 * 60 * 60 * 1000, 2 * 60 * 60 * 1000),
+            Gap::Suspend
+        );
+        // a finished session has nothing left to end
+        taps.load(vec![0, 500]);
+        assert_eq!(gap(&taps, 60_000, 60_000), Gap::Beat);
+    }
+
+    #[test]
+    fn clamps_taps_that_dont_fit() {
+        let week = 7 * 24 * 60 * 60 * 1000;
+        let far = u32::MAX as u64 + 1000;
+        assert!(fits(&[0, 500, 500 + week]));
+        assert!(!fits(&[0, far]));
+        assert!(!fits(&[500, 0]));
+        let mut taps = TapData::default();
+        taps.load(vec![0, 500, 400, far]);
+        assert_eq!(taps.timestamps(), vec![0, 500, 500, 500 + u32::MAX as u64]);
+        // saved sessions that don't fit are turned away instead
+        let saved = format!(r#"{{"timestamps": [0, {far}]}}"#);
+        assert!(serde_json::from_str::<TapData>(&saved).is_err());
+    }
+
+    #[test]
+    fn edits_taps_in_order() {
+        let mut taps = TapData::default();
+        taps.load(vec![0, 500, 1000, 1500]);
+        taps.run(history::Command::Nudge(1, 10));
+        taps.run(history::Command::Nudge(2, -600));
+        assert_eq!(taps.timestamps(), vec![0, 510, 510, 1500]);
+        taps.run(history::Command::Remove(0));
+        assert_eq!(taps.timestamps(), vec![510, 510, 1500]);
+        // edits undo like taps do
+        assert!(taps.undo() && taps.undo());
+        assert_eq!(taps.timestamps(), vec![0, 510, 1000, 1500]);
+    }
+
+    #[test]
+    fn joins_a_split_session() {
+        let start = Instant::now();
+        let tap = |taps: &mut TapData, ms: u64| {
+            taps.run(history::Command::Tap(start + Duration::from_millis(ms)));
+        };
+        let mut taps = TapData::default();
+        for ms in [0, 500, 1000] {
+            tap(&mut taps, ms);
+        }
+        taps.run(history::Command::Reset);
+        tap(&mut taps, 2500);
+        tap(&mut taps, 3000);
+        assert_eq!(taps.previous(), Some((&[0, 500, 1000][..], 1500)));
+        taps.run(history::Command::Join);
+        assert_eq!(taps.timestamps(), vec![0, 500, 1000, 2500, 3000]);
+        assert_eq!(taps.start, Some(start));
+        // joined sessions don't join again, but can be split back apart
+        assert_eq!(taps.previous(), None);
+        assert!(taps.undo());
+        assert_eq!(taps.timestamps(), vec![0, 500]);
+    }
+
+    #[test]
+    fn splits_where_joining_puts_back() {
+        let mut taps = TapData::default();
+        taps.load(vec![0, 500, 1000, 1400, 1800]);
+        taps.run(history::Command::Split(3));
+        assert_eq!(taps.timestamps(), vec![0, 400]);
+        assert_eq!(taps.previous(), Some((&[0, 500, 1000][..], 400)));
+        taps.run(history::Command::Join);
+        assert_eq!(taps.timestamps(), vec![0, 500, 1000, 1400, 1800]);
+        // a split needs taps on both sides
+        taps.run(history::Command::Split(0));
+        assert_eq!(taps.len(), 5);
+    }
+}
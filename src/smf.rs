@@ -0,0 +1,76 @@
+//! A Standard MIDI File writer, just enough for tempo maps DAWs can import
+
+/// Ticks per quarter note
+pub const DIVISION: u16 = 480;
+
+/// A format 0 file whose one track sets each tempo in `tempos`, given as (tick, bpm) pairs
+/// in tick order
+pub fn tempo_map(tempos: &[(u32, f64)]) -> Vec<u8> {
+    let mut track = Vec::new();
+    let mut last_tick = 0;
+    for &(tick, bpm) in tempos {
+        let micros_per_beat = (60_000_000_f64 / bpm)
+            .round()
+            .clamp(1_f64, 0xFF_FFFF as f64) as u32;
+        push_quantity(&mut track, tick - last_tick);
+        track.extend([0xFF, 0x51, 0x03]);
+        track.extend(&micros_per_beat.to_be_bytes()[1..]);
+        last_tick = tick;
+    }
+    // end of track
+    track.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut file = Vec::with_capacity(22 + track.len());
+    file.extend(b"MThd");
+    file.extend(6_u32.to_be_bytes());
+    file.extend(0_u16.to_be_bytes()); // format 0
+    file.extend(1_u16.to_be_bytes()); // one track
+    file.extend(DIVISION.to_be_bytes());
+    file.extend(b"MTrk");
+    file.extend((track.len() as u32).to_be_bytes());
+    file.extend(track);
+    file
+}
+
+/// Appends `value` as a variable length quantity, seven bits a byte, most significant first
+fn push_quantity(bytes: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.extend(groups.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantities() {
+        for (value, expected) in [
+            (0, &[0x00][..]),
+            (0x7F, &[0x7F]),
+            (0x80, &[0x81, 0x00]),
+            (480, &[0x83, 0x60]),
+            (0x0FFF_FFFF, &[0xFF, 0xFF, 0xFF, 0x7F]),
+        ] {
+            let mut bytes = Vec::new();
+            push_quantity(&mut bytes, value);
+            assert_eq!(bytes, expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn one_tempo() {
+        let file = tempo_map(&[(0, 120_f64)]);
+        let expected_track = [
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, 0x00, 0xFF, 0x2F, 0x00,
+        ];
+        assert_eq!(&file[..14], b"MThd\0\0\0\x06\0\0\0\x01\x01\xe0");
+        assert_eq!(&file[14..18], b"MTrk");
+        assert_eq!(&file[18..22], &(expected_track.len() as u32).to_be_bytes());
+        assert_eq!(&file[22..], &expected_track);
+    }
+}
@@ -0,0 +1,159 @@
+use leptos::prelude::*;
+use leptos_use::{use_interval_fn_with_options, UseIntervalFnOptions};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::UrlSearchParams;
+
+use crate::{Metric, TapData};
+
+/// Milliseconds between synthetic taps, 400 bpm, unless the minimum gap needs them slower
+const INTERVAL_MS: u64 = 150;
+/// Synthetic taps come at least this long after the minimum gap, so it drops none of them
+const GAP_MARGIN_MS: u64 = 10;
+/// Estimator latency is sampled once per this many synthetic taps
+const SAMPLE_EVERY: usize = 20;
+
+/// Returns true if the page was opened with `?soak`, the developer flag for this mode
+pub fn is_enabled() -> bool {
+    window()
+        .location()
+        .search()
+        .and_then(|search| UrlSearchParams::new_with_str(&search))
+        .is_ok_and(|params| params.has("soak"))
+}
+
+#[derive(Clone, Default)]
+struct SoakStats {
+    injected: usize,
+    /// Resets seen mid-soak, which means a timeout fired despite constant tapping
+    resets: usize,
+    /// Milliseconds each estimator took on the data at the last sample
+    latencies: Vec<(&'static str, f64)>,
+}
+
+/// Injects synthetic taps at a high rate until stopped, to soak-test memory growth,
+/// estimator latency, and the reset timeout
+#[derive(Clone, Copy)]
+pub struct SoakTest {
+    running: RwSignal<bool>,
+    stats: RwSignal<SoakStats>,
+    interval_ms: Signal<u64>,
+}
+
+fn now_ms() -> f64 {
+    window().performance().map_or(0_f64, |p| p.now())
+}
+
+/// Bytes of linear memory the WASM instance has grown to
+fn wasm_memory_bytes() -> Option<f64> {
+    let buffer =
+        js_sys::Reflect::get(&wasm_bindgen::memory(), &JsValue::from_str("buffer")).ok()?;
+    Some(buffer.dyn_into::<js_sys::ArrayBuffer>().ok()?.byte_length() as f64)
+}
+
+/// Soaks with taps spaced to get past `min_gap_ms`, the minimum gap of the current mode
+pub fn use_soak_test(
+    tap_data: ReadSignal<TapData>,
+    min_gap_ms: Signal<u64>,
+    on_tap: impl Fn() + Clone + 'static,
+) -> SoakTest {
+    let soak = SoakTest {
+        running: RwSignal::new(false),
+        stats: RwSignal::new(SoakStats::default()),
+        interval_ms: Signal::derive(move || INTERVAL_MS.max(min_gap_ms.get() + GAP_MARGIN_MS)),
+    };
+
+    let ticker = use_interval_fn_with_options(
+        move || {
+            let was_reset = tap_data.with_untracked(|taps| taps.start.is_none());
+            on_tap();
+            let mut stats = soak.stats.write();
+            if was_reset && stats.injected > 0 {
+                stats.resets += 1;
+            }
+            stats.injected += 1;
+            if stats.injected.is_multiple_of(SAMPLE_EVERY) {
+                stats.latencies = tap_data.with_untracked(|taps| {
+                    Metric::ALL
+                        .iter()
+                        .map(|metric| {
+                            let start = now_ms();
+                            let _ = (metric.estimator())(&taps.timestamps());
+                            (metric.label(), now_ms() - start)
+                        })
+                        .collect()
+                });
+            }
+        },
+        soak.interval_ms,
+        UseIntervalFnOptions {
+            immediate: false,
+            immediate_callback: false,
+        },
+    );
+
+    Effect::new(move |_| {
+        if soak.running.get() {
+            soak.stats.set(SoakStats::default());
+            (ticker.resume)();
+        } else {
+            (ticker.pause)();
+        }
+    });
+
+    soak
+}
+
+#[component]
+pub fn SoakControls(soak: SoakTest, tap_data: ReadSignal<TapData>) -> impl IntoView {
+    let summary = move || {
+        let stats = soak.stats.get();
+        let taps_kib = tap_data.with(TapData::memory_bytes) as f64 / 1024_f64;
+        let wasm_mib = wasm_memory_bytes().unwrap_or(0_f64) / (1024_f64 * 1024_f64);
+        let mut rows = vec![
+            (
+                "injected",
+                format!("{:6} ", stats.injected),
+                "synthetic taps".to_string(),
+            ),
+            (
+                "resets",
+                format!("{:6} ", stats.resets),
+                "should stay 0 while soaking".into(),
+            ),
+            (
+                "taps-mem",
+                format!("{taps_kib:6.0} "),
+                "KiB of timestamps".into(),
+            ),
+            (
+                "wasm-mem",
+                format!("{wasm_mib:6.1} "),
+                "MiB of linear memory".into(),
+            ),
+        ];
+        for (label, ms) in stats.latencies {
+            rows.push((label, format!("{ms:6.2} "), "ms per estimate".into()));
+        }
+        rows.into_iter()
+            .map(|(label, value, description)| {
+                view! {
+                    <span class="text-label">{format!("{label:>12}: ")}</span>
+                    <span class="text-value">{value}</span>
+                    <span class="text-comment">"# "{description}"\n"</span>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <span class="text-label">"        soak:  "</span>
+        <button
+            class="hover:text-value"
+            on:mousedown=move |_| soak.running.update(|running| *running = !*running)
+        >
+            {move || if soak.running.get() { "stop " } else { "start" }}
+        </button>
+        <span class="text-comment">" # synthetic taps every "{soak.interval_ms}"ms\n"</span>
+        <Show when=move || soak.running.get()>{summary}</Show>
+    }
+}
@@ -0,0 +1,71 @@
+use leptos::prelude::*;
+
+/// Milliseconds after navigation the page should be ready for its first tap by
+pub const BUDGET_MS: f64 = 1_000.0;
+
+type Marks = RwSignal<Vec<(&'static str, f64)>, LocalStorage>;
+
+thread_local! {
+    static MARKS: Marks = RwSignal::new_local(Vec::new());
+}
+
+/// Notes that startup reached `stage`, timed from navigation (performance.now)
+pub fn mark(stage: &'static str) {
+    let now = window().performance().map_or(0_f64, |p| p.now());
+    MARKS.with(|marks| marks.write().push((stage, now)));
+}
+
+/// How long each stage of startup took to reach, for the soak screen
+#[component]
+pub fn StartupTimes() -> impl IntoView {
+    let marks = MARKS.with(|marks| *marks);
+    move || {
+        marks
+            .get()
+            .into_iter()
+            .map(|(stage, ms)| {
+                let note = if ms > BUDGET_MS {
+                    "over budget"
+                } else {
+                    "after load"
+                };
+                view! {
+                    <span class="text-label">{format!("{stage:>12}: ")}</span>
+                    <span class="text-value">{format!("{ms:6.0} ")}</span>
+                    <span class="text-comment">"# ms "{note}"\n"</span>
+                }
+            })
+            .collect_view()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use web_time::{Duration, Instant};
+
+    use crate::{history, Metric, TapData};
+
+    #[test]
+    fn long_session_estimates() {
+        // a running session of 500 taps at 120 bpm, recorded the way the app records them
+        let mut taps = TapData::default();
+        let start = Instant::now();
+        for beat in 0..500 {
+            let now = start + Duration::from_millis(beat * 500);
+            taps.run(history::Command::Tap(now));
+        }
+        assert_eq!(taps.len(), 500);
+        assert!(!taps.is_reset());
+        let offsets = taps.timestamps();
+        for metric in Metric::ALL {
+            let estimate = metric.estimate(&offsets);
+            let label = metric.label();
+            assert!(
+                estimate
+                    .as_ref()
+                    .is_ok_and(|bpm| (bpm - 120_f64).abs() < 1e-6),
+                "{label} gave {estimate:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use leptos::prelude::*;
+use serde::Serialize;
+
+use crate::bpm::{self, median, thiel_sen_fit, BpmCalculationError};
+use crate::{export, schema, smf, Stepper};
+
+/// The tempo of each beat between taps, from the median interval within `smoothing` beats
+/// of it, so 0 follows every tap exactly and larger values ride out tapping jitter
+pub fn tempo_curve(offsets: &[u64], smoothing: usize) -> Vec<f64> {
+    let intervals: Vec<f64> = offsets.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+    (0..intervals.len())
+        .map(|beat| {
+            let window =
+                beat.saturating_sub(smoothing)..(beat + smoothing + 1).min(intervals.len());
+            60_000_f64 / median(intervals[window].to_vec())
+        })
+        .collect()
+}
+
+/// A MIDI tempo map with a tempo change on every beat that needs one
+pub fn tempo_map_smf(curve: &[f64]) -> Vec<u8> {
+    let mut tempos: Vec<(u32, f64)> = Vec::new();
+    for (beat, &bpm) in curve.iter().enumerate() {
+        if tempos
+            .last()
+            .is_none_or(|&(_, last)| (last - bpm).abs() >= 0.005)
+        {
+            tempos.push((beat as u32 * smf::DIVISION as u32, bpm));
+        }
+    }
+    smf::tempo_map(&tempos)
+}
+
+/// The tempo curve as a CSV of the seconds into the session each beat starts at, and its bpm
+pub fn tempo_map_csv(offsets: &[u64], curve: &[f64]) -> String {
+    let mut csv = String::from("time,bpm\n");
+    for (offset, bpm) in offsets.iter().zip(curve) {
+        let seconds = (offset - offsets[0]) as f64 / 1000_f64;
+        csv.push_str(&format!("{seconds:.3},{bpm:.3}\n"));
+    }
+    csv
+}
+
+/// One beat of the fitted grid and the tap aimed at it, in seconds from the first tap
+#[derive(Debug, Serialize)]
+pub struct WarpMarker {
+    pub beat: usize,
+    pub time: f64,
+    pub tap: f64,
+}
+
+/// The thiel-sen beat grid laid over the session, for warping a recording of it
+#[derive(Debug, Serialize)]
+pub struct WarpMarkers {
+    pub bpm: f64,
+    pub markers: Vec<WarpMarker>,
+}
+
+pub fn warp_markers(offsets: &[u64]) -> Result<WarpMarkers, BpmCalculationError> {
+    let fit = thiel_sen_fit(offsets)?;
+    let start = offsets[0] as f64;
+    let markers = offsets
+        .iter()
+        .enumerate()
+        .map(|(beat, &tap)| WarpMarker {
+            beat,
+            time: (fit.offset_of(beat as f64) - start) / 1000_f64,
+            tap: (tap as f64 - start) / 1000_f64,
+        })
+        .collect();
+    Ok(WarpMarkers {
+        bpm: bpm::finite(fit.bpm())?,
+        markers,
+    })
+}
+
+/// Downloads the session's tempo curve as a MIDI file or CSV, or one steady tempo
+#[component]
+pub fn TempoMapExport(offsets: Memo<Vec<u64>>, bpm: Signal<Option<f64>>) -> impl IntoView {
+    let smoothing = RwSignal::new(2);
+    let steady = move |_| {
+        if let Some(bpm) = bpm.get_untracked() {
+            export::download_bytes("tempo.mid", "audio/midi", &tempo_map_smf(&[bpm]));
+        }
+    };
+    let curve = move || {
+        tempo_curve(
+            &offsets.read_untracked(),
+            smoothing.get_untracked() as usize,
+        )
+    };
+    let midi = move |_| {
+        let curve = curve();
+        if !curve.is_empty() {
+            export::download_bytes("tempo-map.mid", "audio/midi", &tempo_map_smf(&curve));
+        }
+    };
+    let csv = move |_| {
+        let curve = curve();
+        if !curve.is_empty() {
+            let csv = tempo_map_csv(&offsets.read_untracked(), &curve);
+            export::download_text("tempo-map.csv", "text/csv", &csv);
+        }
+    };
+
+    let warp = move |_| {
+        if let Ok(markers) = warp_markers(&offsets.read_untracked()) {
+            export::download_text(
+                "warp-markers.json",
+                "application/json",
+                &schema::to_json(&markers),
+            );
+        }
+    };
+
+    view! {
+        <span class="text-label">"   tempo-map:  "</span>
+        <button class="hover:text-value" on:mousedown=steady>
+            "steady"
+        </button>
+        " "
+        <button class="hover:text-value" on:mousedown=midi>
+            "curve"
+        </button>
+        " "
+        <button class="hover:text-value" on:mousedown=csv>
+            "csv"
+        </button>
+        <span class="text-comment">" # midi, csv of time,bpm\n"</span>
+        <Stepper
+            label="map-smooth"
+            value=smoothing.read_only()
+            set_value=smoothing.write_only()
+            min=0
+            max=9
+            description=Signal::derive(move || match smoothing.get() {
+                0 => "curve follows every tap".to_string(),
+                beats => format!("median of {} beats", 2 * beats + 1),
+            })
+        />
+        <span class="text-label">"        warp:  "</span>
+        <button class="hover:text-value" on:mousedown=warp>
+            "export"
+        </button>
+        <span class="text-comment">" # json beat grid markers\n"</span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_follows_or_smooths_the_taps() {
+        let offsets = [0, 500, 1000, 1400, 1900, 2400];
+        assert_eq!(
+            tempo_curve(&offsets, 0),
+            vec![120.0, 120.0, 150.0, 120.0, 120.0]
+        );
+        assert_eq!(tempo_curve(&offsets, 1), vec![120.0; 5]);
+        assert!(tempo_curve(&[0], 1).is_empty());
+    }
+
+    #[test]
+    fn csv_times_each_beat() {
+        let offsets = [1000, 1500, 1900];
+        let csv = tempo_map_csv(&offsets, &tempo_curve(&offsets, 0));
+        assert_eq!(csv, "time,bpm\n0.000,120.000\n0.500,150.000\n");
+    }
+
+    #[test]
+    fn warp_markers_sit_on_the_grid() {
+        // the late middle tap is outvoted, so its beat stays on the grid
+        let warp = warp_markers(&[1000, 1500, 2100, 2500, 3000]).unwrap();
+        assert!((warp.bpm - 120_f64).abs() < 1e-9);
+        for (marker, (time, tap)) in
+            warp.markers
+                .iter()
+                .zip([(0.0, 0.0), (0.5, 0.5), (1.0, 1.1), (1.5, 1.5), (2.0, 2.0)])
+        {
+            assert!((marker.time - time).abs() < 1e-9, "{marker:?}");
+            assert!((marker.tap - tap).abs() < 1e-9, "{marker:?}");
+        }
+    }
+
+    #[test]
+    fn unchanged_tempos_are_left_out() {
+        let curve = [120_f64, 120_f64, 150_f64];
+        assert_eq!(
+            tempo_map_smf(&curve),
+            smf::tempo_map(&[(0, 120_f64), (2 * smf::DIVISION as u32, 150_f64)])
+        );
+    }
+}
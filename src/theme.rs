@@ -0,0 +1,59 @@
+use leptos::prelude::*;
+
+use crate::Stepper;
+
+/// A color scheme for the whole screen
+/// Components only use the theme's color roles (`text-label`, `text-value`, `bg-screen`, ...),
+/// and each skin sets those roles in `style/tailwind.css`
+#[derive(Debug, Clone, Copy)]
+pub enum Skin {
+    Classic,
+    Phosphor,
+    Amber,
+    Solarized,
+}
+
+impl Skin {
+    pub const ALL: [Skin; 4] = [Self::Classic, Self::Phosphor, Self::Amber, Self::Solarized];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Phosphor => "green phosphor",
+            Self::Amber => "amber",
+            Self::Solarized => "solarized",
+        }
+    }
+
+    /// Class that sets the skin's colors on everything inside it
+    pub fn class(&self) -> &'static str {
+        match self {
+            Self::Classic => "skin-classic",
+            Self::Phosphor => "skin-phosphor",
+            Self::Amber => "skin-amber",
+            Self::Solarized => "skin-solarized",
+        }
+    }
+}
+
+/// 1-based index into `Skin::ALL`
+pub fn skin(index: u64) -> Skin {
+    Skin::ALL[index as usize - 1]
+}
+
+#[component]
+pub fn SkinControls(
+    skin_index: ReadSignal<u64>,
+    set_skin_index: WriteSignal<u64>,
+) -> impl IntoView {
+    view! {
+        <Stepper
+            label="skin"
+            value=skin_index
+            set_value=set_skin_index
+            min=1
+            max=Skin::ALL.len() as u64
+            description=Signal::derive(move || skin(skin_index.get()).name().to_string())
+        />
+    }
+}
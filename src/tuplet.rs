@@ -0,0 +1,26 @@
+use leptos::prelude::*;
+
+use crate::music::{modulate, MODULATIONS};
+
+/// Related tempos for planning metric modulations from the primary estimate
+#[component]
+pub fn TupletTable(bpm: Signal<Option<f64>>) -> impl IntoView {
+    view! {
+        "\n"
+        {MODULATIONS
+            .into_iter()
+            .map(|(p, q, name)| {
+                view! {
+                    <span class="text-label">{format!("{:>12}: ", format!("×{p}/{q}"))}</span>
+                    <span class="text-value">
+                        {move || match bpm.get() {
+                            Some(bpm) => format!("{:6.2} ", modulate(bpm, p, q)),
+                            None => "000.00 ".into(),
+                        }}
+                    </span>
+                    <span class="text-comment">"# "{name}"\n"</span>
+                }
+            })
+            .collect_view()}
+    }
+}
@@ -0,0 +1,98 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::{use_document_visibility, use_interval_fn};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{RequestCache, RequestInit, Response, VisibilityState};
+
+/// How often an open page looks for a newer deploy, in milliseconds
+const CHECK_MS: u64 = 30 * 60 * 1000;
+
+/// The build a page loads, named by its hashed wasm file
+fn build_of(html: &str) -> Option<&str> {
+    let end = html.find("_bg.wasm")?;
+    let start = html[..end].rfind(['/', '"', '\''])? + 1;
+    Some(&html[start..end])
+}
+
+/// The build the deployed page would load now, skipping every cache on the way
+async fn deployed_build() -> Option<String> {
+    let init = RequestInit::new();
+    init.set_cache(RequestCache::NoStore);
+    let url = window().location().href().ok()?;
+    let response = JsFuture::from(window().fetch_with_str_and_init(&url, &init))
+        .await
+        .ok()?
+        .dyn_into::<Response>()
+        .ok()?;
+    let html = JsFuture::from(response.text().ok()?)
+        .await
+        .ok()?
+        .as_string()?;
+    build_of(&html).map(str::to_string)
+}
+
+/// Offers a reload once a newer version is deployed than the one running
+#[component]
+pub fn UpdateOffer() -> impl IntoView {
+    let running = document()
+        .document_element()
+        .and_then(|root| build_of(&root.outer_html()).map(str::to_string));
+    let (available, set_available) = signal(false);
+    let check = move || {
+        let Some(running) = running.clone() else {
+            return;
+        };
+        spawn_local(async move {
+            if deployed_build()
+                .await
+                .is_some_and(|deployed| deployed != running)
+            {
+                set_available.set(true);
+            }
+        });
+    };
+
+    // installed pages can sit open for days, so coming back to one checks too
+    let visibility = use_document_visibility();
+    Effect::new({
+        let check = check.clone();
+        move |_| {
+            if visibility.get() == VisibilityState::Visible {
+                check();
+            }
+        }
+    });
+    let _interval = use_interval_fn(check, CHECK_MS);
+
+    view! {
+        <Show when=move || available.get()>
+            <span class="text-label">"      update:  "</span>
+            <button
+                class="hover:text-value"
+                on:mousedown=move |_| {
+                    let _ = window().location().reload();
+                }
+            >
+                "reload"
+            </button>
+            <span class="text-comment">" # a new version is out\n"</span>
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_hashed_build() {
+        let html = r#"<link rel="preload" href="/tools/bpm/bpm-leptos-3f2a_bg.wasm" as="fetch">"#;
+        assert_eq!(build_of(html), Some("bpm-leptos-3f2a"));
+        assert_eq!(
+            build_of(r#"href="bpm-leptos-99_bg.wasm""#),
+            Some("bpm-leptos-99")
+        );
+        assert_eq!(build_of("<html></html>"), None);
+    }
+}
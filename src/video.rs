@@ -0,0 +1,133 @@
+use leptos::html::Video;
+use leptos::prelude::*;
+use web_sys::{HtmlInputElement, Url};
+
+use crate::bpm;
+use crate::rate::format_rate;
+
+type Estimator = fn(&[u64]) -> Result<f64, bpm::BpmCalculationError>;
+/// The taps skip over whatever was seeked past, which autocorrelation doesn't mind
+#[cfg(feature = "autocorrelation")]
+const ESTIMATOR: Estimator = bpm::autocorrelation;
+#[cfg(not(feature = "autocorrelation"))]
+const ESTIMATOR: Estimator = bpm::thiel_sen;
+
+/// Taps made against a video's own clock, so pausing and seeking don't throw them off
+#[derive(Clone, Copy)]
+pub struct VideoSync {
+    video: NodeRef<Video>,
+    /// Object url of the loaded file
+    source: RwSignal<Option<String>>,
+    /// Milliseconds into the video of each tap, in order
+    taps: RwSignal<Vec<u64>>,
+}
+
+impl VideoSync {
+    /// Notes a tap made `latency_ms` ago at the video's time then, if it is playing
+    pub fn on_tap(&self, latency_ms: f64) {
+        let Some(video) = self.video.get_untracked() else {
+            return;
+        };
+        if video.paused() {
+            return;
+        }
+        let time = (video.current_time() * 1_000_f64 - latency_ms).round() as u64;
+        self.taps.update(|taps| {
+            let at = taps.partition_point(|&tap| tap <= time);
+            taps.insert(at, time);
+        });
+    }
+
+    fn open(&self, input: &HtmlInputElement) {
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        if let Some(previous) = self.source.get_untracked() {
+            let _ = Url::revoke_object_url(&previous);
+        }
+        self.source
+            .set(Url::create_object_url_with_blob(&file).ok());
+        self.taps.write().clear();
+    }
+
+    /// Forgets the taps after a seek, so going back retakes the video from there
+    fn seeked(&self) {
+        if let Some(video) = self.video.get_untracked() {
+            let time = (video.current_time() * 1_000_f64) as u64;
+            self.taps.update(|taps| taps.retain(|&tap| tap < time));
+        }
+    }
+}
+
+pub fn use_video_sync() -> VideoSync {
+    VideoSync {
+        video: NodeRef::new(),
+        source: RwSignal::new(None),
+        taps: RwSignal::new(Vec::new()),
+    }
+}
+
+/// `ms` as minutes and seconds, like a video player shows it
+fn timestamp(ms: u64) -> String {
+    let seconds = ms / 1_000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[component]
+pub fn VideoControls(video: VideoSync) -> impl IntoView {
+    view! {
+        <span class="text-label">"       video:  "</span>
+        <label class="hover:text-value cursor-pointer">
+            "open "
+            <input
+                type="file"
+                accept="video/*"
+                class="hidden"
+                on:change=move |evt| video.open(&event_target::<HtmlInputElement>(&evt))
+            />
+        </label>
+        <span class="text-comment">" # tap along to a local video\n"</span>
+    }
+}
+
+/// The loaded video, with the tempo of the taps made along to it
+#[component]
+pub fn VideoPane(video: VideoSync, slow: ReadSignal<bool>) -> impl IntoView {
+    let bpm = Memo::new(move |_| ESTIMATOR(&video.taps.read()).ok());
+    let span = move || {
+        video.taps.with(|taps| match (taps.first(), taps.last()) {
+            (Some(&first), Some(&last)) if taps.len() > 1 => {
+                format!("# {} to {}\n", timestamp(first), timestamp(last))
+            }
+            _ => "# tap while it plays\n".to_string(),
+        })
+    };
+
+    view! {
+        <Show when=move || video.source.read().is_some()>
+            <video
+                node_ref=video.video
+                class="w-full max-h-[40vh]"
+                controls
+                src=move || video.source.get()
+                on:seeked=move |_| video.seeked()
+            />
+            "\n"
+            <span class="text-label">{format!("{:>12}: ", "video")}</span>
+            <span class="text-value">{move || format_rate(bpm.get(), slow.get())}</span>
+            <span class="text-comment">{span}</span>
+        </Show>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_read_like_a_player() {
+        assert_eq!(timestamp(0), "0:00");
+        assert_eq!(timestamp(83_999), "1:23");
+        assert_eq!(timestamp(3_600_000), "60:00");
+    }
+}
@@ -0,0 +1,57 @@
+use leptos::prelude::*;
+use leptos_use::{use_document_visibility, use_interval_fn};
+use web_sys::VisibilityState;
+
+use crate::TapData;
+
+/// Milliseconds between the heartbeats that check timers run on time
+const HEARTBEAT_MS: u64 = 1000;
+/// A heartbeat this many milliseconds late means timers are being throttled
+const LATE_MS: f64 = 1000.0;
+
+#[derive(Clone, Copy)]
+struct Idle(Signal<bool>);
+
+/// Tracks whether the app is idle, hidden with no session running, so animation loops and
+/// flashes can stop instead of draining battery in the background
+/// Tab audio detection keeps listening, since it is how a hidden tab starts a session
+pub fn use_idle_watchdog(tap_data: ReadSignal<TapData>) {
+    let visibility = use_document_visibility();
+    let idle = Signal::derive(move || {
+        visibility.get() == VisibilityState::Hidden && tap_data.with(|taps| taps.start.is_none())
+    });
+    provide_context(Idle(idle));
+}
+
+/// True while background work should be suspended, once `use_idle_watchdog` has run
+pub fn idle() -> Signal<bool> {
+    let Idle(idle) = use_context().expect("use_idle_watchdog should run before idle");
+    idle
+}
+
+/// Sets `throttled` when a steady heartbeat fires late, as timers do in background tabs and
+/// low-power modes, which means the reset timeout can't be relied on to fire on time
+pub fn use_throttle_detector(throttled: RwSignal<bool>) {
+    let idle = idle();
+    let last = StoredValue::new(None::<f64>);
+    let heartbeat = use_interval_fn(
+        move || {
+            let now = window().performance().map_or(0_f64, |p| p.now());
+            let previous = last.get_value();
+            last.set_value(Some(now));
+            if previous.is_some_and(|previous| now - previous > HEARTBEAT_MS as f64 + LATE_MS) {
+                throttled.set(true);
+            }
+        },
+        HEARTBEAT_MS,
+    );
+    // an idle app has no reset to miss, and its first beat back isn't a late one
+    Effect::new(move |_| {
+        if idle.get() {
+            (heartbeat.pause)();
+            last.set_value(None);
+        } else {
+            (heartbeat.resume)();
+        }
+    });
+}
@@ -0,0 +1,148 @@
+use js_sys::{Object, Reflect};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::use_debounce_fn;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{RequestInit, Response};
+
+/// Tempo changes settle for this long before they are posted, in milliseconds
+const DEBOUNCE_MS: f64 = 1000.0;
+const TEMPLATE: &str = r#"{"bpm": {bpm}}"#;
+
+#[derive(Debug, Clone, PartialEq)]
+enum HookState {
+    Off,
+    /// A url is set, but there was no tempo to post yet
+    Waiting,
+    Posted(f64),
+    Failed(String),
+}
+
+/// Tempo updates posted to a url of the user's, for home automations and the like
+#[derive(Clone, Copy)]
+pub struct Webhook {
+    url: RwSignal<Option<String>>,
+    /// The body posted, with `{bpm}` standing in for the tempo
+    template: RwSignal<String>,
+    state: RwSignal<HookState>,
+}
+
+/// `template` with the tempo filled in, if that makes valid json
+fn render(template: &str, bpm: f64) -> Option<String> {
+    let body = template.replace("{bpm}", &format!("{bpm:.2}"));
+    serde_json::from_str::<serde_json::Value>(&body).ok()?;
+    Some(body)
+}
+
+async fn post(url: &str, body: &str) -> Result<(), String> {
+    let headers = Object::new();
+    let _ = Reflect::set(&headers, &"Content-Type".into(), &"application/json".into());
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_headers(&headers);
+    init.set_body(&JsValue::from_str(body));
+    let response = JsFuture::from(window().fetch_with_str_and_init(url, &init))
+        .await
+        .map_err(|_| "couldn't reach the url".to_string())?
+        .unchecked_into::<Response>();
+    if response.ok() {
+        Ok(())
+    } else {
+        Err(format!("the url answered {}", response.status()))
+    }
+}
+
+/// Posts `bpm` to the webhook's url once it has settled
+pub fn use_webhook(bpm: Signal<Option<f64>>) -> Webhook {
+    let webhook = Webhook {
+        url: RwSignal::new(None),
+        template: RwSignal::new(TEMPLATE.into()),
+        state: RwSignal::new(HookState::Off),
+    };
+    let send = use_debounce_fn(
+        move || {
+            let (Some(url), Some(bpm)) = (webhook.url.get_untracked(), bpm.get_untracked()) else {
+                return;
+            };
+            let Some(body) = render(&webhook.template.get_untracked(), bpm) else {
+                webhook
+                    .state
+                    .set(HookState::Failed("body isn't json".into()));
+                return;
+            };
+            spawn_local(async move {
+                webhook.state.set(match post(&url, &body).await {
+                    Ok(()) => HookState::Posted(bpm),
+                    Err(e) => HookState::Failed(e),
+                });
+            });
+        },
+        DEBOUNCE_MS,
+    );
+    Effect::new(move |_| {
+        // a reset leaves the last tempo posted
+        if bpm.get().is_some() && webhook.url.read().is_some() {
+            send();
+        }
+    });
+
+    webhook
+}
+
+#[component]
+pub fn WebhookControls(webhook: Webhook) -> impl IntoView {
+    let set_url = move |url: String| {
+        let url = url.trim().to_string();
+        if url.is_empty() {
+            webhook.state.set(HookState::Off);
+            webhook.url.set(None);
+        } else {
+            webhook.state.set(HookState::Waiting);
+            webhook.url.set(Some(url));
+        }
+    };
+    let status = move || match webhook.state.get() {
+        HookState::Off => "enter posts the tempo here".to_string(),
+        HookState::Waiting => "posts once there's a tempo".to_string(),
+        HookState::Posted(bpm) => format!("posted {bpm:.2}"),
+        HookState::Failed(e) => e,
+    };
+
+    view! {
+        <span class="text-label">"     webhook:  "</span>
+        <input
+            class="bg-field outline-none w-[12ch] text-value"
+            placeholder="url"
+            on:keydown=move |evt| {
+                if evt.key() == "Enter" {
+                    set_url(event_target_value(&evt));
+                }
+            }
+        />
+        <span class="text-comment">" # "{status}"\n"</span>
+        <span class="text-label">"        body:  "</span>
+        <input
+            class="bg-field outline-none w-[24ch] text-value"
+            prop:value=move || webhook.template.get()
+            on:change=move |evt| webhook.template.set(event_target_value(&evt))
+        />
+        <span class="text-comment">" # {bpm} is the tempo\n"</span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_body() {
+        assert_eq!(render(TEMPLATE, 120.5), Some(r#"{"bpm": 120.50}"#.into()));
+        let template = r#"{"entity": "light.desk", "tempo": {bpm}}"#;
+        assert_eq!(
+            render(template, 90.0).as_deref(),
+            Some(r#"{"entity": "light.desk", "tempo": 90.00}"#)
+        );
+        assert_eq!(render("bpm={bpm}", 90.0), None);
+    }
+}
@@ -0,0 +1,158 @@
+/// Signatures of the records a zip archive is read through
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+/// Size of the end of directory record, before its comment
+const END_OF_DIRECTORY_LEN: usize = 22;
+
+/// The data of a file in a zip archive, as it's stored there
+#[derive(Debug, PartialEq)]
+pub enum Data<'a> {
+    Stored(&'a [u8]),
+    /// Compressed with raw deflate, which the browser inflates
+    Deflated(&'a [u8]),
+    /// Compressed some other way, or encrypted
+    Unsupported,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Entry<'a> {
+    /// Path of the file within the archive
+    pub name: String,
+    pub data: Data<'a>,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<usize> {
+    let bytes = bytes.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    let bytes = bytes.get(at..at + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The files in the zip archive `bytes`, leaving out folders, or None if it isn't one
+pub fn entries(bytes: &[u8]) -> Option<Vec<Entry<'_>>> {
+    // the end of directory record is last, only followed by a comment of its own
+    let end = (0..=bytes.len().checked_sub(END_OF_DIRECTORY_LEN)?)
+        .rev()
+        .find(|&at| u32_at(bytes, at) == Some(END_OF_DIRECTORY))?;
+    let count = u16_at(bytes, end + 10)?;
+    let mut at = u32_at(bytes, end + 16)? as usize;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(bytes, at)? != DIRECTORY_ENTRY {
+            return None;
+        }
+        let (flags, method) = (u16_at(bytes, at + 8)?, u16_at(bytes, at + 10)?);
+        let size = u32_at(bytes, at + 20)? as usize;
+        let name_len = u16_at(bytes, at + 28)?;
+        let skipped = name_len + u16_at(bytes, at + 30)? + u16_at(bytes, at + 32)?;
+        let local = u32_at(bytes, at + 42)? as usize;
+        let name = String::from_utf8_lossy(bytes.get(at + 46..at + 46 + name_len)?).into_owned();
+        at += 46 + skipped;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        // the local header repeats the name, with an extra field of its own
+        if u32_at(bytes, local)? != LOCAL_HEADER {
+            return None;
+        }
+        let start = local + 30 + u16_at(bytes, local + 26)? + u16_at(bytes, local + 28)?;
+        let data = bytes.get(start..start + size)?;
+        let encrypted = flags & 1 == 1;
+        let data = match method {
+            0 if !encrypted => Data::Stored(data),
+            8 if !encrypted => Data::Deflated(data),
+            _ => Data::Unsupported,
+        };
+        entries.push(Entry { name, data });
+    }
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zip archive of `files` as (name, compression method, data)
+    fn archive(files: &[(&str, u16, &[u8])]) -> Vec<u8> {
+        let (mut bytes, mut directory) = (Vec::new(), Vec::new());
+        for &(name, method, data) in files {
+            let sizes = [(data.len() as u32).to_le_bytes(); 2].concat();
+            let local = bytes.len() as u32;
+            bytes.extend(LOCAL_HEADER.to_le_bytes());
+            bytes.extend([20, 0, 0, 0]);
+            bytes.extend(method.to_le_bytes());
+            bytes.extend([0; 8]);
+            bytes.extend(&sizes);
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend([0, 0]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(data);
+
+            directory.extend(DIRECTORY_ENTRY.to_le_bytes());
+            directory.extend([20, 0, 20, 0, 0, 0]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0; 8]);
+            directory.extend(&sizes);
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(local.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let offset = bytes.len() as u32;
+        let count = (files.len() as u16).to_le_bytes();
+        bytes.extend(&directory);
+        bytes.extend(END_OF_DIRECTORY.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(count);
+        bytes.extend(count);
+        bytes.extend((directory.len() as u32).to_le_bytes());
+        bytes.extend(offset.to_le_bytes());
+        bytes.extend([0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn lists_the_files() {
+        let bytes = archive(&[
+            ("taps/", 0, b""),
+            ("taps/a.csv", 0, b"0.5\n1.0\n"),
+            ("taps/b.csv", 8, b"\x01\x02"),
+            ("taps/c.csv", 12, b"\x03"),
+        ]);
+        let entries = entries(&bytes).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    name: "taps/a.csv".into(),
+                    data: Data::Stored(b"0.5\n1.0\n"),
+                },
+                Entry {
+                    name: "taps/b.csv".into(),
+                    data: Data::Deflated(b"\x01\x02"),
+                },
+                Entry {
+                    name: "taps/c.csv".into(),
+                    data: Data::Unsupported,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(entries(b"0.5,1.0"), None);
+        assert_eq!(entries(&[]), None);
+        let bytes = archive(&[("a.csv", 0, b"0.5\n")]);
+        // cut off partway through the file's data
+        let mut truncated = bytes[..40].to_vec();
+        truncated.extend(&bytes[bytes.len() - 22..]);
+        assert_eq!(entries(&truncated), None);
+        assert_eq!(entries(&archive(&[])), Some(vec![]));
+    }
+}
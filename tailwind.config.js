@@ -7,7 +7,19 @@ module.exports = {
     },
   },
   theme: {
-    extend: {},
+    extend: {
+      // set per skin in style/tailwind.css, see src/theme.rs
+      colors: {
+        screen: "rgb(var(--screen) / <alpha-value>)",
+        field: "rgb(var(--field) / <alpha-value>)",
+        ink: "rgb(var(--ink) / <alpha-value>)",
+        label: "rgb(var(--label) / <alpha-value>)",
+        value: "rgb(var(--value) / <alpha-value>)",
+        comment: "rgb(var(--comment) / <alpha-value>)",
+        dim: "rgb(var(--dim) / <alpha-value>)",
+        alert: "rgb(var(--alert) / <alpha-value>)",
+      },
+    },
   },
   plugins: [],
 }